@@ -0,0 +1,177 @@
+//! Resolution micro-benchmarks.  Run with `cargo bench`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rust_container::{Container, ContainerResult};
+use std::any::{Any, TypeId};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher};
+use std::rc::Rc;
+
+#[derive(Clone, Default)]
+struct FxBuildHasher;
+
+impl BuildHasher for FxBuildHasher {
+    type Hasher = FxHasher;
+
+    fn build_hasher(&self) -> FxHasher {
+        FxHasher { hash: 0 }
+    }
+}
+
+struct FxHasher {
+    hash: u64
+}
+
+impl Hasher for FxHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.write_u64(u64::from_ne_bytes(word));
+        }
+    }
+
+    fn write_u64(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95);
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+// A `TypeId` carrying its precomputed hash, the way the default entry map keys its entries.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct PrehashedKey {
+    type_id: TypeId,
+    hash: u64
+}
+
+impl Hash for PrehashedKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+#[derive(Default)]
+struct PrehashedHasher {
+    hash: u64
+}
+
+impl Hasher for PrehashedHasher {
+    fn write(&mut self, _bytes: &[u8]) {
+        unreachable!("PrehashedKey only writes its hash")
+    }
+
+    fn write_u64(&mut self, hash: u64) {
+        self.hash = hash;
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+#[derive(Clone)]
+struct Config {
+    retries: u32
+}
+
+#[derive(Clone)]
+struct Service {
+    retries: u32
+}
+
+fn register(container: &Container) {
+    container
+        .register_instance(Config { retries: 3 })
+        .register_factory(|container: &Container| -> ContainerResult<Service> {
+            let config: Config = container.default()?;
+            Ok(Service { retries: config.retries })
+        });
+    container
+        .register_instance(1u8)
+        .register_instance(2u16)
+        .register_instance(3u32)
+        .register_instance(4u64)
+        .register_instance(5i8)
+        .register_instance(6i16)
+        .register_instance(7i32)
+        .register_instance(8i64)
+        .register_instance(String::from("nine"));
+}
+
+fn warm_singleton(c: &mut Criterion) {
+    let mut group = c.benchmark_group("warm singleton");
+    for (hasher, container) in [("default hasher", Container::new()), ("fx hasher", Container::with_hasher(FxBuildHasher))].iter() {
+        register(container);
+        container.default::<Service>().unwrap();
+        group.bench_function(*hasher, |b| b.iter(|| black_box(container.default::<Service>().unwrap().retries)));
+    }
+    group.finish();
+}
+
+// The path that hashes the `TypeId` once for both the lookup and the caching of the new instance.
+fn first_resolution(c: &mut Criterion) {
+    let mut group = c.benchmark_group("first resolution");
+    for (hasher, container) in [("default hasher", Container::new()), ("fx hasher", Container::with_hasher(FxBuildHasher))].iter() {
+        register(container);
+        group.bench_function(*hasher, |b| b.iter(|| {
+            container.reset_cache();
+            black_box(container.default::<Service>().unwrap().retries)
+        }));
+    }
+    group.finish();
+}
+
+fn cold_singleton(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cold singleton");
+    group.bench_function("default hasher", |b| b.iter(|| {
+        let container = Container::new();
+        register(&container);
+        black_box(container.default::<Service>().unwrap().retries)
+    }));
+    group.bench_function("fx hasher", |b| b.iter(|| {
+        let container = Container::with_hasher(FxBuildHasher);
+        register(&container);
+        black_box(container.default::<Service>().unwrap().retries)
+    }));
+    group.finish();
+}
+
+// Looking up a factory and caching the instance it built, before and after the `TypeId` was hashed
+// once for both.  Modeled on bare maps, since the entry map itself is private to the crate.
+fn entry_caching(c: &mut Criterion) {
+    let mut group = c.benchmark_group("entry caching");
+    let type_ids = [TypeId::of::<Config>(), TypeId::of::<Service>(), TypeId::of::<u8>(), TypeId::of::<String>()];
+    let instance: Rc<dyn Any> = Rc::new(Config { retries: 3 });
+
+    let mut hashed_twice: HashMap<TypeId, Rc<dyn Any>, RandomState> = HashMap::default();
+    for type_id in type_ids.iter() {
+        hashed_twice.insert(*type_id, instance.clone());
+    }
+    group.bench_function("hash twice", |b| b.iter(|| {
+        for type_id in type_ids.iter() {
+            let entry = hashed_twice.get(black_box(type_id)).cloned().unwrap();
+            hashed_twice.insert(*type_id, entry);
+        }
+    }));
+
+    let random_state = RandomState::new();
+    let mut hashed_once: HashMap<PrehashedKey, Rc<dyn Any>, BuildHasherDefault<PrehashedHasher>> = HashMap::default();
+    for type_id in type_ids.iter() {
+        hashed_once.insert(PrehashedKey { type_id: *type_id, hash: random_state.hash_one(type_id) }, instance.clone());
+    }
+    group.bench_function("hash once", |b| b.iter(|| {
+        for type_id in type_ids.iter() {
+            let key = PrehashedKey { type_id: *black_box(type_id), hash: random_state.hash_one(type_id) };
+            let entry = hashed_once.get(&key).cloned().unwrap();
+            hashed_once.insert(key, entry);
+        }
+    }));
+    group.finish();
+}
+
+criterion_group!(benches, warm_singleton, first_resolution, cold_singleton, entry_caching);
+criterion_main!(benches);
@@ -1,4 +1,7 @@
-use rust_container::{Container, ContainerResult};
+#![allow(clippy::arc_with_non_send_sync)]
+
+use rust_container::{Container, ContainerResult, Module};
+use std::convert::TryFrom;
 use std::sync::Arc;
 
 // Example traits + structs
@@ -99,6 +102,10 @@ impl GroceryStore for KMart {
     }
 }
 
+struct ClosingNotice {
+    store: Arc<dyn GroceryStore>
+}
+
 struct BasicThing {
     id: i32
 }
@@ -113,35 +120,49 @@ enum GroceryStoreType {
     Kmart
 }
 
-impl From<GroceryStoreType> for i32 {
-    fn from(enum_value: GroceryStoreType) -> i32 {
-        enum_value as i32
+impl From<GroceryStoreType> for i64 {
+    fn from(enum_value: GroceryStoreType) -> i64 {
+        enum_value as i64
     }
 }
 
-impl From<i32> for GroceryStoreType {
-    fn from(value: i32) -> GroceryStoreType {
+impl TryFrom<i64> for GroceryStoreType {
+    type Error = i64;
+
+    fn try_from(value: i64) -> Result<GroceryStoreType, i64> {
         match value {
-            0 => GroceryStoreType::WholeFoods,
-            1 => GroceryStoreType::Kmart,
-            _ => panic!("nope")
+            0 => Ok(GroceryStoreType::WholeFoods),
+            1 => Ok(GroceryStoreType::Kmart),
+            _ => Err(value)
         }
     }
 }
 
+struct GroceryModule;
+
+impl Module for GroceryModule {
+    fn register(&self, container: &Container) {
+        container
+            .register_auto2(|fruit, meat| -> Arc<dyn GroceryStore> { Arc::new(WholeFoods::new(fruit, meat)) })
+            .register_specialized_factory(GroceryStoreType::WholeFoods, |container: &Container| -> ContainerResult<Arc<dyn GroceryStore>> { Ok(Arc::new(WholeFoods::new(container.default()?, container.default()?))) })
+            .register_specialized_factory(GroceryStoreType::Kmart, |_container: &Container| -> ContainerResult<Arc<dyn GroceryStore>> { Ok(Arc::new(KMart::new())) })
+            .register_factory_using_specialized(GroceryStoreType::Kmart, |_container: &Container, kmart: Arc<dyn GroceryStore>| -> ContainerResult<Arc<ClosingNotice>> { Ok(Arc::new(ClosingNotice { store: kmart })) });
+    }
+}
+
 fn main() {
     let container = Box::new(Container::new());
 
     container
+        .record_dependencies()
         .register_factory(|_container: &Container| -> ContainerResult<Arc<Banana>> { Ok(Arc::new(Banana::new(false))) })
-        .register_factory(|_container: &Container| -> ContainerResult<Arc<dyn Fruit>> { Ok(Arc::new(Banana::new(true))) })
+        .register_dyn_factory::<dyn Fruit, _, _>(|_container: &Container| Ok(Banana::new(true)), |banana| banana)
         .register_factory(|_container: &Container| -> ContainerResult<Arc<Chicken>> { Ok(Arc::new(Chicken::new(true))) })
-        .register_factory(|_container: &Container| -> ContainerResult<Arc<dyn Meat>> { Ok(Arc::new(Chicken::new(false))) })
-        .register_factory(|container: &Container| -> ContainerResult<Arc<dyn GroceryStore>> { Ok(Arc::new(WholeFoods::new(container.default()?, container.default()?))) })
+        .register_shared_as::<Chicken, dyn Meat>(|chicken| chicken)
+        .register_coercion::<Banana, dyn Fruit>(|banana| banana)
+        .install(&[Box::new(GroceryModule)])
         .register_factory(|_container: &Container| -> ContainerResult<Arc<BasicThing>> { Ok(Arc::new(BasicThing { id: 42 })) })
-        .register_factory(|_container: &Container| -> ContainerResult<Arc<BasicThingWithLifetime>> { Ok(Arc::new(BasicThingWithLifetime { name: "foobar" })) })
-        .register_specialized_factory(GroceryStoreType::WholeFoods, |container: &Container| -> ContainerResult<Arc<dyn GroceryStore>> { Ok(Arc::new(WholeFoods::new(container.default()?, container.default()?))) })
-        .register_specialized_factory(GroceryStoreType::Kmart, |_container: &Container| -> ContainerResult<Arc<dyn GroceryStore>> { Ok(Arc::new(KMart::new())) });
+        .register_factory(|_container: &Container| -> ContainerResult<Arc<BasicThingWithLifetime>> { Ok(Arc::new(BasicThingWithLifetime { name: "foobar" })) });
 
     let grocery_store: Arc<dyn GroceryStore> = container.default().unwrap();
     grocery_store.print_inventory();
@@ -149,6 +170,10 @@ fn main() {
     let basic_thing: Arc<BasicThing> = container.default().unwrap();
     println!("basic_thing id = {}", basic_thing.id);
 
+    let borrowed_basic_thing = container.borrow_instance::<Arc<BasicThing>>().unwrap();
+    println!("borrowed basic_thing id = {}", borrowed_basic_thing.id);
+    drop(borrowed_basic_thing);
+
     let basic_thing_2: Arc<BasicThingWithLifetime> = container.default().unwrap();
     println!("basic_thing_2 name = {}", basic_thing_2.name);
 
@@ -161,6 +186,17 @@ fn main() {
     let the_same_chicken: Arc<Chicken> = container.default().unwrap();
     println!("the_same_chicken is organic? {}", the_same_chicken.is_organic());
 
+    let meat: Arc<dyn Meat> = container.default().unwrap();
+    println!("meat is the same chicken? {}", Arc::ptr_eq(&(chicken.clone() as Arc<dyn Meat>), &meat));
+
+    for fruit in container.resolve_all_coerced::<dyn Fruit>().unwrap().iter() {
+        println!("coerced fruit: {}", fruit.name());
+    }
+
+    let concrete_chicken: Arc<Chicken> = container.resolve_concrete::<dyn Meat, Chicken>().unwrap();
+    println!("concrete chicken is organic? {}", concrete_chicken.is_organic());
+    println!("fruit is a banana? {}", container.resolve_concrete::<dyn Fruit, Banana>().is_ok());
+
     let specialized_whole_foods: Arc<dyn GroceryStore> = container.specialized(GroceryStoreType::WholeFoods).unwrap();
     specialized_whole_foods.print_inventory();
 
@@ -168,7 +204,14 @@ fn main() {
     specialized_kmart.print_inventory();
 
     let all_grocery_stores: Vec<Arc<dyn GroceryStore>> = container.all_specialized::<Arc<dyn GroceryStore>, GroceryStoreType>().unwrap();
-    for (_i, grocery_store) in all_grocery_stores.iter().enumerate() {
+    for grocery_store in all_grocery_stores.iter() {
         grocery_store.print_inventory();
     }
+
+    container.for_each_specialized::<Arc<dyn GroceryStore>, GroceryStoreType>(|grocery_store| grocery_store.print_inventory()).unwrap();
+
+    let closing_notice: Arc<ClosingNotice> = container.default().unwrap();
+    closing_notice.store.print_inventory();
+
+    print!("{}", container.dependency_graph().to_dot());
 }
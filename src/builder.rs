@@ -0,0 +1,56 @@
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{Container, ContainerError, Module};
+
+pub struct ContainerBuilder {
+    container: Container<'static>
+}
+
+impl ContainerBuilder {
+    pub fn new() -> Self {
+        Self {
+            container: Container::new()
+        }
+    }
+
+    pub fn configure(self, register: impl FnOnce(&Container)) -> Self {
+        register(&self.container);
+        self
+    }
+
+    pub fn install(self, modules: &[Box<dyn Module>]) -> Self {
+        self.container.install(modules);
+        self
+    }
+
+    pub fn require<T>(self) -> Self where
+        T : 'static
+    {
+        self.container.require::<T>();
+        self
+    }
+
+    pub fn build(self) -> Container<'static> {
+        self.container
+    }
+
+    /// Builds the container only if every declared requirement is registered, reporting all
+    /// problems found rather than the first: one `UnsatisfiedRequirements` per missing type.
+    pub fn build_validated(self) -> Result<Container<'static>, Vec<ContainerError>> {
+        let unsatisfied_requirements = self.container.unsatisfied_requirements();
+        if unsatisfied_requirements.is_empty() {
+            return Ok(self.container);
+        }
+        Err(unsatisfied_requirements.into_iter()
+            .map(|type_name| ContainerError::UnsatisfiedRequirements { type_names: vec![type_name] })
+            .collect())
+    }
+}
+
+impl Default for ContainerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
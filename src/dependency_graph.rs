@@ -0,0 +1,54 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+#[derive(Clone, Debug, Default)]
+pub struct DependencyGraph {
+    edges: BTreeMap<&'static str, Vec<&'static str>>
+}
+
+impl DependencyGraph {
+    pub fn new() -> Self {
+        Self {
+            edges: BTreeMap::new()
+        }
+    }
+
+    pub fn edges(&self) -> &BTreeMap<&'static str, Vec<&'static str>> {
+        &self.edges
+    }
+
+    pub fn dependencies_of(&self, type_name: &str) -> &[&'static str] {
+        match self.edges.get(type_name) {
+            Some(dependencies) => dependencies.as_slice(),
+            None => &[]
+        }
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph dependencies {\n");
+        for (type_name, dependencies) in self.edges.iter() {
+            if dependencies.is_empty() {
+                writeln!(dot, "    \"{}\";", type_name).unwrap();
+            }
+            for dependency in dependencies.iter() {
+                writeln!(dot, "    \"{}\" -> \"{}\";", type_name, dependency).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub(crate) fn add_node(&mut self, type_name: &'static str) {
+        self.edges.entry(type_name).or_default();
+    }
+
+    pub(crate) fn add_edge(&mut self, from: &'static str, to: &'static str) {
+        self.add_node(to);
+        let dependencies = self.edges.entry(from).or_default();
+        if !dependencies.contains(&to) {
+            dependencies.push(to);
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use core::any::TypeId;
+#[cfg(feature = "std")]
+use core::hash::{BuildHasher, Hash, Hasher};
+#[cfg(feature = "std")]
+use alloc::rc::Rc;
+#[cfg(feature = "std")]
+use std::collections::hash_map::RandomState;
+
+use crate::ContainerEntry;
+
+#[cfg(feature = "std")]
+type HashTypeId = Rc<dyn Fn(&TypeId) -> u64>;
+
+#[cfg(feature = "std")]
+type Entries = std::collections::HashMap<EntryKey, ContainerEntry, PrehashedState>;
+#[cfg(not(feature = "std"))]
+type Entries = alloc::collections::BTreeMap<TypeId, ContainerEntry>;
+
+/// A `TypeId` together with its hash under the map's hasher.  Resolution computes it once and
+/// reuses it for the lookup and for caching the built instance.
+#[derive(Clone, Copy)]
+pub(crate) struct EntryKey {
+    type_id: TypeId,
+    #[cfg(feature = "std")]
+    hash: u64
+}
+
+impl PartialEq for EntryKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.type_id == other.type_id
+    }
+}
+
+impl Eq for EntryKey { }
+
+#[cfg(feature = "std")]
+impl Hash for EntryKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Default)]
+struct PrehashedState;
+
+#[cfg(feature = "std")]
+impl BuildHasher for PrehashedState {
+    type Hasher = PrehashedHasher;
+
+    fn build_hasher(&self) -> PrehashedHasher {
+        PrehashedHasher { hash: 0 }
+    }
+}
+
+#[cfg(feature = "std")]
+struct PrehashedHasher {
+    hash: u64
+}
+
+#[cfg(feature = "std")]
+impl Hasher for PrehashedHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes.iter() {
+            self.hash = self.hash.rotate_left(8) ^ u64::from(*byte);
+        }
+    }
+
+    fn write_u64(&mut self, hash: u64) {
+        self.hash = hash;
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+// The default hasher is called directly; a custom one sits behind a closure so `Container` doesn't
+// need a hasher type parameter.  Only containers built with `with_hasher` pay for the indirection.
+#[cfg(feature = "std")]
+#[derive(Clone)]
+enum KeyHasher {
+    Random(RandomState),
+    Custom(HashTypeId)
+}
+
+#[cfg(feature = "std")]
+impl KeyHasher {
+    fn hash(&self, type_id: &TypeId) -> u64 {
+        match self {
+            KeyHasher::Random(random_state) => random_state.hash_one(type_id),
+            KeyHasher::Custom(hash_type_id) => hash_type_id(type_id)
+        }
+    }
+}
+
+/// The default registration map.  Every `default` call goes through it, so it is the one map whose
+/// hasher can be configured.
+#[derive(Clone)]
+pub(crate) struct EntryMap {
+    entries: Entries,
+    #[cfg(feature = "std")]
+    key_hasher: KeyHasher
+}
+
+impl Default for EntryMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EntryMap {
+    pub(crate) fn new() -> Self {
+        Self {
+            entries: Entries::default(),
+            #[cfg(feature = "std")]
+            key_hasher: KeyHasher::Random(RandomState::new())
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn with_hasher<S>(build_hasher: S) -> Self where
+        S : BuildHasher + 'static
+    {
+        Self {
+            entries: Entries::default(),
+            key_hasher: KeyHasher::Custom(Rc::new(move |type_id: &TypeId| -> u64 { build_hasher.hash_one(type_id) }))
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Entries::with_capacity_and_hasher(capacity, PrehashedState),
+            key_hasher: KeyHasher::Random(RandomState::new())
+        }
+    }
+
+    pub(crate) fn key(&self, type_id: TypeId) -> EntryKey {
+        EntryKey {
+            type_id,
+            #[cfg(feature = "std")]
+            hash: self.key_hasher.hash(&type_id)
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn get_by_key(&self, key: &EntryKey) -> Option<&ContainerEntry> {
+        self.entries.get(key)
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn get_by_key(&self, key: &EntryKey) -> Option<&ContainerEntry> {
+        self.entries.get(&key.type_id)
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn insert_by_key(&mut self, key: EntryKey, container_entry: ContainerEntry) -> Option<ContainerEntry> {
+        self.entries.insert(key, container_entry)
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn insert_by_key(&mut self, key: EntryKey, container_entry: ContainerEntry) -> Option<ContainerEntry> {
+        self.entries.insert(key.type_id, container_entry)
+    }
+
+    #[cfg(feature = "std")]
+    fn remove_by_key(&mut self, key: &EntryKey) -> Option<ContainerEntry> {
+        self.entries.remove(key)
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn remove_by_key(&mut self, key: &EntryKey) -> Option<ContainerEntry> {
+        self.entries.remove(&key.type_id)
+    }
+
+    pub(crate) fn get(&self, type_id: &TypeId) -> Option<&ContainerEntry> {
+        self.get_by_key(&self.key(*type_id))
+    }
+
+    pub(crate) fn insert(&mut self, type_id: TypeId, container_entry: ContainerEntry) -> Option<ContainerEntry> {
+        self.insert_by_key(self.key(type_id), container_entry)
+    }
+
+    pub(crate) fn insert_if_absent(&mut self, type_id: TypeId, container_entry: ContainerEntry) {
+        let key = self.key(type_id);
+        if self.get_by_key(&key).is_none() {
+            self.insert_by_key(key, container_entry);
+        }
+    }
+
+    pub(crate) fn remove(&mut self, type_id: &TypeId) -> Option<ContainerEntry> {
+        self.remove_by_key(&self.key(*type_id))
+    }
+
+    pub(crate) fn contains_key(&self, type_id: &TypeId) -> bool {
+        self.get(type_id).is_some()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&TypeId, &ContainerEntry)> {
+        self.entries.iter().map(|(key, container_entry)| (&key.type_id, container_entry))
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&TypeId, &ContainerEntry)> {
+        self.entries.iter()
+    }
+
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &TypeId> {
+        self.iter().map(|(type_id, _)| type_id)
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn into_entries(self) -> impl Iterator<Item = (TypeId, ContainerEntry)> {
+        self.entries.into_iter().map(|(key, container_entry)| (key.type_id, container_entry))
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn into_entries(self) -> impl Iterator<Item = (TypeId, ContainerEntry)> {
+        self.entries.into_iter()
+    }
+}
@@ -1,41 +1,227 @@
-use std::any::{Any, TypeId};
-use std::cell::RefCell;
-use std::collections::{HashMap, HashSet};
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        log::debug!($($arg)*);
+    }
+}
+
+macro_rules! log_trace {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        log::trace!($($arg)*);
+    }
+}
+
+macro_rules! register_auto {
+    ($name:ident, $($dependency:ident),+) => {
+        pub fn $name<T, $($dependency,)+ F>(&self, build: F) -> &Self where
+            T : Clone + 'static,
+            $($dependency : Clone + 'static,)+
+            F : Fn($($dependency),+) -> T + 'static
+        {
+            self.register_factory(move |container: &Container| -> ContainerResult<T> {
+                Ok(build($(container.default::<$dependency>()?),+))
+            })
+        }
+    }
+}
+
+/// Registers `value` as an `Arc` singleton and links each listed trait-object view to that same
+/// instance, e.g. `register_all_views!(container, Banana::new(true) => [dyn Fruit])`.
+#[macro_export]
+macro_rules! register_all_views {
+    ($container:expr, $value:expr => [$($view:ty),* $(,)?]) => {{
+        fn link_view<Concrete, Dyn>(container: &$crate::Container, _instance: &$crate::__private::Arc<Concrete>, coerce: fn($crate::__private::Arc<Concrete>) -> $crate::__private::Arc<Dyn>) where
+            Concrete : 'static,
+            Dyn : ?Sized + 'static
+        {
+            container.link_concrete_to_trait(coerce);
+        }
+
+        let container: &$crate::Container = &$container;
+        let instance = $crate::__private::Arc::new($value);
+        container.register_arc(instance.clone());
+        $(link_view::<_, $view>(container, &instance, |concrete| concrete);)*
+        container
+    }};
+}
+
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::sync::Arc;
+}
+
+mod builder;
+mod dependency_graph;
+mod entry_map;
+mod metadata;
+mod registration;
+
+pub use builder::ContainerBuilder;
+pub use dependency_graph::DependencyGraph;
+pub use metadata::{ContainerMetadata, EntryMetadata};
+pub use registration::RegistrationBuilder;
+
+use entry_map::EntryMap;
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::any::{type_name, Any, TypeId};
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::iter;
+use core::cell::{Cell, OnceCell, Ref, RefCell};
+use core::fmt::{Debug, Display, Formatter};
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use core::hash::BuildHasher;
+use core::hash::{Hash, Hasher};
+use core::mem;
+use core::ops::{Deref, Range};
+#[cfg(feature = "std")]
 use std::error::Error;
-use std::fmt::{Display, Formatter};
-use std::hash::{Hash, Hasher};
-use std::marker::PhantomData;
-use std::sync::Arc;
+#[cfg(feature = "std")]
+use std::thread;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "std")]
+pub(crate) type Map<K, V> = std::collections::HashMap<K, V>;
+#[cfg(feature = "std")]
+pub(crate) type Set<T> = std::collections::HashSet<T>;
+#[cfg(not(feature = "std"))]
+pub(crate) type Map<K, V> = alloc::collections::BTreeMap<K, V>;
+#[cfg(not(feature = "std"))]
+pub(crate) type Set<T> = alloc::collections::BTreeSet<T>;
+
+#[cfg(feature = "std")]
+pub type BoxedError = Box<dyn Error>;
+#[cfg(not(feature = "std"))]
+pub type BoxedError = Box<dyn core::fmt::Debug>;
 
 #[derive(Debug)]
 pub enum ContainerError {
-    MissingEntry,
-    MissingSpecializedEntry,
-    FactoryError { error: Box<dyn Error> }
+    MissingEntry { type_name: String },
+    MissingSpecializedEntry { type_name: String },
+    MissingNamedEntry { type_name: String },
+    SpecializationCollision { specialization_value: i64 },
+    WrongPhase { phase: Phase },
+    AlreadyResolved,
+    WrongEntryKind { expected: EntryKind, found: EntryKind },
+    BudgetExceeded,
+    DepthExceeded,
+    UnsatisfiedRequirements { type_names: Vec<String> },
+    DowncastFailure,
+    CircularDependency { type_name: String },
+    InvalidSpecialization { specialization_value: i64 },
+    NotRegisteredAsTrait { type_name: String, implementors: Vec<String> },
+    FactoryError { error: BoxedError }
 }
 
 impl Display for ContainerError {
-    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
         match self {
-            ContainerError::MissingEntry => write!(f, "MissingEntry"),
-            ContainerError::MissingSpecializedEntry => write!(f, "MissingSpecializedEntry"),
+            ContainerError::MissingEntry { type_name } => write!(f, "MissingEntry({})", type_name),
+            ContainerError::MissingSpecializedEntry { type_name } => write!(f, "MissingSpecializedEntry({})", type_name),
+            ContainerError::MissingNamedEntry { type_name } => write!(f, "MissingNamedEntry({})", type_name),
+            ContainerError::SpecializationCollision { specialization_value } => write!(f, "SpecializationCollision({})", specialization_value),
+            ContainerError::WrongPhase { phase } => write!(f, "WrongPhase({:?})", phase),
+            ContainerError::AlreadyResolved => write!(f, "AlreadyResolved"),
+            ContainerError::WrongEntryKind { expected, found } => write!(f, "WrongEntryKind(expected {:?}, found {:?})", expected, found),
+            ContainerError::BudgetExceeded => write!(f, "BudgetExceeded"),
+            ContainerError::DepthExceeded => write!(f, "DepthExceeded"),
+            ContainerError::UnsatisfiedRequirements { type_names } => write!(f, "UnsatisfiedRequirements({})", type_names.join(", ")),
+            ContainerError::DowncastFailure => write!(f, "DowncastFailure"),
+            ContainerError::CircularDependency { type_name } => write!(f, "CircularDependency({})", type_name),
+            ContainerError::InvalidSpecialization { specialization_value } => write!(f, "InvalidSpecialization({})", specialization_value),
+            ContainerError::NotRegisteredAsTrait { type_name, implementors } => write!(f, "NotRegisteredAsTrait({} is implemented by registered {} but not registered itself)", type_name, implementors.join(", ")),
             ContainerError::FactoryError { error: _ } => write!(f, "FactoryError")
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for ContainerError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
-            ContainerError::MissingEntry => None,
-            ContainerError::MissingSpecializedEntry => None,
+            ContainerError::MissingEntry { type_name: _ } => None,
+            ContainerError::MissingSpecializedEntry { type_name: _ } => None,
+            ContainerError::MissingNamedEntry { type_name: _ } => None,
+            ContainerError::SpecializationCollision { specialization_value: _ } => None,
+            ContainerError::WrongPhase { phase: _ } => None,
+            ContainerError::AlreadyResolved => None,
+            ContainerError::WrongEntryKind { expected: _, found: _ } => None,
+            ContainerError::BudgetExceeded => None,
+            ContainerError::DepthExceeded => None,
+            ContainerError::UnsatisfiedRequirements { type_names: _ } => None,
+            ContainerError::DowncastFailure => None,
+            ContainerError::CircularDependency { type_name: _ } => None,
+            ContainerError::InvalidSpecialization { specialization_value: _ } => None,
+            ContainerError::NotRegisteredAsTrait { type_name: _, implementors: _ } => None,
             ContainerError::FactoryError { error } => Some(error.as_ref())
         }
     }
 }
 
+impl ContainerError {
+    pub fn is_missing(&self) -> bool {
+        matches!(self, ContainerError::MissingEntry { type_name: _ } | ContainerError::MissingSpecializedEntry { type_name: _ } | ContainerError::MissingNamedEntry { type_name: _ } | ContainerError::NotRegisteredAsTrait { type_name: _, implementors: _ })
+    }
+
+    pub fn is_factory_error(&self) -> bool {
+        matches!(self, ContainerError::FactoryError { error: _ })
+    }
+}
+
 pub type ContainerResult<T> = Result<T, ContainerError>;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EntryKind {
+    Default,
+    Specialized,
+    Named
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EntryState {
+    Instance,
+    Factory,
+    SpecializedFactory,
+    Cached,
+    Transient,
+    Scoped,
+    Disabled,
+    Rc,
+    Memoized,
+    Coercion
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lifetime {
+    Singleton,
+    Transient,
+    Scoped
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Registering,
+    Resolving
+}
+
 struct KnownSpecializationKey {
     specialization_type_id: TypeId,
     type_id: TypeId
@@ -52,7 +238,7 @@ impl KnownSpecializationKey {
     fn new_for_specialization<T, S>() -> Self where
         T : Clone + 'static,
         S : Copy + 'static,
-        i32 : From<S>
+        i64 : From<S>
     {
         let type_id = TypeId::of::<T>();
         let specialization_type_id = TypeId::of::<S>();
@@ -69,6 +255,18 @@ impl PartialEq for KnownSpecializationKey {
 
 impl Eq for KnownSpecializationKey { }
 
+impl PartialOrd for KnownSpecializationKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for KnownSpecializationKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.type_id, self.specialization_type_id).cmp(&(other.type_id, other.specialization_type_id))
+    }
+}
+
 impl Hash for KnownSpecializationKey {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.type_id.hash(state);
@@ -78,23 +276,20 @@ impl Hash for KnownSpecializationKey {
 
 impl Clone for KnownSpecializationKey {
     fn clone(&self) -> Self {
-        Self {
-            type_id: self.type_id,
-            specialization_type_id: self.specialization_type_id
-        }
+        *self
     }
 }
 
 impl Copy for KnownSpecializationKey { }
 
 struct SpecializedEntryKey {
-    specialization_value: i32,
+    specialization_value: i64,
     specialization_type_id: TypeId,
     type_id: TypeId
 }
 
 impl SpecializedEntryKey {
-    fn new(type_id: TypeId, specialization_type_id: TypeId, specialization_value: i32) -> Self {
+    fn new(type_id: TypeId, specialization_type_id: TypeId, specialization_value: i64) -> Self {
         SpecializedEntryKey {
             type_id,
             specialization_type_id,
@@ -105,10 +300,10 @@ impl SpecializedEntryKey {
     fn new_for_specialization<T, S>(specialization: S) -> Self where
         T : Clone + 'static,
         S : Copy + 'static,
-        i32 : From<S>,
-        S : From<i32>
+        i64 : From<S>,
+        S : TryFrom<i64>
     {
-        let specialization_value: i32 = specialization.into();
+        let specialization_value: i64 = specialization.into();
         let type_id = TypeId::of::<T>();
         let specialization_type_id = TypeId::of::<S>();
         SpecializedEntryKey::new(type_id, specialization_type_id, specialization_value)
@@ -125,6 +320,19 @@ impl PartialEq for SpecializedEntryKey {
 
 impl Eq for SpecializedEntryKey { }
 
+impl PartialOrd for SpecializedEntryKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SpecializedEntryKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.type_id, self.specialization_type_id, self.specialization_value)
+            .cmp(&(other.type_id, other.specialization_type_id, other.specialization_value))
+    }
+}
+
 impl Hash for SpecializedEntryKey {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.type_id.hash(state);
@@ -135,20 +343,51 @@ impl Hash for SpecializedEntryKey {
 
 impl Clone for SpecializedEntryKey {
     fn clone(&self) -> Self {
-        SpecializedEntryKey {
-            type_id: self.type_id,
-            specialization_type_id: self.specialization_type_id,
-            specialization_value: self.specialization_value
-        }
+        *self
     }
 }
 
 impl Copy for SpecializedEntryKey { }
 
+pub type Resolver<T> = Box<dyn Fn(&Container) -> ContainerResult<T>>;
+
+/// A function registered as a service.  `Arc` keeps it `Clone` so it resolves like any other `T`.
+pub type SharedFn<A, R> = Arc<dyn Fn(A) -> R + Send + Sync>;
+
+type NamedEntryKey = (TypeId, String);
+
+pub type AnyFactory = Arc<dyn Fn(&Container) -> ContainerResult<Arc<dyn Any>>>;
+
+type TypeIdResolver = Arc<dyn Fn(TypeId) -> Option<Arc<dyn Any>>>;
+
+/// Records that `Arc<Concrete>` can be viewed as a trait object, so a miss on that trait object can
+/// point at the concrete registration instead of reporting a bare `MissingEntry`.
+struct TraitWitness {
+    concrete_type_id: TypeId,
+    concrete_name: &'static str
+}
+
+type MissingHandler = Arc<dyn Fn(&Container, TypeId)>;
+
+type Disposer = Box<dyn Fn()>;
+
+type Override = Box<dyn FnOnce(&Container)>;
+
 enum ContainerEntry {
     Instance(Arc<dyn Any>),
-    Factory(Arc<dyn Fn(&Container) -> ContainerResult<Arc<dyn Any>>>),
-    SpecializedFactory(Arc<dyn Fn(&Container) -> ContainerResult<Arc<dyn Any>>>)
+    Factory(AnyFactory),
+    SpecializedFactory(AnyFactory)
+}
+
+impl ContainerEntry {
+    fn state(&self, resolved_from_factory: bool) -> EntryState {
+        match self {
+            ContainerEntry::Instance(_) if resolved_from_factory => EntryState::Cached,
+            ContainerEntry::Instance(_) => EntryState::Instance,
+            ContainerEntry::Factory(_) => EntryState::Factory,
+            ContainerEntry::SpecializedFactory(_) => EntryState::SpecializedFactory
+        }
+    }
 }
 
 impl Clone for ContainerEntry {
@@ -167,44 +406,546 @@ impl Clone for ContainerEntry {
     }
 }
 
+fn specialization_from_value<S>(specialization_value: i64) -> ContainerResult<S> where
+    S : TryFrom<i64>
+{
+    match S::try_from(specialization_value) {
+        Ok(specialization) => Ok(specialization),
+        Err(_) => Err(ContainerError::InvalidSpecialization { specialization_value })
+    }
+}
+
+fn downcast_instance<T>(instance: &Arc<dyn Any>) -> ContainerResult<T> where
+    T : Clone + 'static
+{
+    match instance.downcast_ref::<T>() {
+        Some(instance) => Ok(instance.clone()),
+        None => Err(ContainerError::DowncastFailure)
+    }
+}
+
+#[derive(Clone)]
+struct TaggedEntry {
+    type_id: TypeId,
+    factory: AnyFactory,
+    instance: Option<Arc<dyn Any>>
+}
+
+#[cfg(feature = "std")]
+type ArgFactory<T, A> = Box<dyn Fn(&Container, &A) -> ContainerResult<T>>;
+
+#[cfg(feature = "std")]
+struct MemoizedFactory<T, A> {
+    factory: ArgFactory<T, A>,
+    instances: RefCell<std::collections::HashMap<A, T>>
+}
+
+struct ShadowedEntry {
+    entry: Option<ContainerEntry>,
+    resolved_factory: Option<AnyFactory>
+}
+
+pub trait Module {
+    fn register(&self, container: &Container);
+}
+
+pub trait Dispose {
+    fn dispose(&self);
+}
+
+pub struct InstanceRef<'a, T> {
+    instance: Ref<'a, T>
+}
+
+impl<'a, T> Deref for InstanceRef<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.instance
+    }
+}
+
+pub enum InstanceCow<'a, T> {
+    Borrowed(InstanceRef<'a, T>),
+    Owned(T)
+}
+
+impl<'a, T> InstanceCow<'a, T> where
+    T : Clone
+{
+    pub fn into_owned(self) -> T {
+        match self {
+            InstanceCow::Borrowed(instance) => (*instance).clone(),
+            InstanceCow::Owned(instance) => instance
+        }
+    }
+}
+
+impl<'a, T> Deref for InstanceCow<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            InstanceCow::Borrowed(instance) => instance,
+            InstanceCow::Owned(instance) => instance
+        }
+    }
+}
+
+pub struct Lazy<'a, 'container, T> {
+    container: &'a Container<'container>,
+    value: OnceCell<T>
+}
+
+impl<'a, 'container, T> Lazy<'a, 'container, T> where
+    T : Clone + 'static
+{
+    pub fn get(&self) -> ContainerResult<&T> {
+        if let Some(value) = self.value.get() {
+            return Ok(value);
+        }
+        let value = self.container.default::<T>()?;
+        Ok(self.value.get_or_init(|| value))
+    }
+
+    pub fn is_resolved(&self) -> bool {
+        self.value.get().is_some()
+    }
+}
+
+pub struct LifetimeHandle<'a, 'container> {
+    container: &'a Container<'container>,
+    type_id: TypeId
+}
+
+impl<'a, 'container> LifetimeHandle<'a, 'container> {
+    pub fn lifetime(&self) -> Lifetime {
+        self.container.lifetime_of_type(self.type_id).unwrap_or(Lifetime::Singleton)
+    }
+
+    /// Switches the registration's lifetime.  Going transient or scoped discards any cached
+    /// instance; a scoped registration is then built once per container that resolves it.
+    pub fn set_lifetime(&self, lifetime: Lifetime) {
+        let scoped_factory = self.container.scoped_factories.borrow_mut().remove(&self.type_id);
+        if let Some(factory) = scoped_factory {
+            self.container.entries.borrow_mut().insert_if_absent(self.type_id, ContainerEntry::Factory(factory));
+        }
+        match lifetime {
+            Lifetime::Singleton => {
+                self.container.transient_entries.borrow_mut().remove(&self.type_id);
+            },
+            Lifetime::Transient => {
+                self.container.transient_entries.borrow_mut().insert(self.type_id);
+                let resolved_factory = self.container.resolved_factories.borrow_mut().remove(&self.type_id);
+                if let Some(factory) = resolved_factory {
+                    self.container.entries.borrow_mut().insert(self.type_id, ContainerEntry::Factory(factory));
+                }
+                self.container.cached_instances.borrow_mut().remove(&self.type_id);
+            },
+            Lifetime::Scoped => {
+                self.container.transient_entries.borrow_mut().remove(&self.type_id);
+                let resolved_factory = self.container.resolved_factories.borrow_mut().remove(&self.type_id);
+                let factory = match resolved_factory {
+                    Some(factory) => Some(factory),
+                    None => match self.container.entries.borrow().get(&self.type_id) {
+                        Some(ContainerEntry::Factory(factory)) => Some(factory.clone()),
+                        _ => None
+                    }
+                };
+                if let Some(factory) = factory {
+                    self.container.entries.borrow_mut().remove(&self.type_id);
+                    self.container.cached_instances.borrow_mut().remove(&self.type_id);
+                    self.container.scoped_factories.borrow_mut().insert(self.type_id, factory);
+                }
+            }
+        }
+    }
+}
+
+pub struct OverrideSet {
+    overrides: Vec<Override>
+}
+
+impl OverrideSet {
+    pub fn new() -> Self {
+        Self {
+            overrides: Vec::new()
+        }
+    }
+
+    pub fn instance<T>(mut self, instance: T) -> Self where
+        T : Clone + 'static
+    {
+        self.overrides.push(Box::new(move |container: &Container| { container.register_instance(instance); }));
+        self
+    }
+
+    pub fn factory<T, F>(mut self, factory: F) -> Self where
+        T : Clone + 'static,
+        F : Fn(&Container) -> Result<T, ContainerError> + 'static
+    {
+        self.overrides.push(Box::new(move |container: &Container| { container.register_factory(factory); }));
+        self
+    }
+}
+
+impl Default for OverrideSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct SpecializedIter<'a, 'container, T, S> {
+    container: &'a Container<'container>,
+    specialization_values: alloc::vec::IntoIter<i64>,
+    spooky_ghost: PhantomData<fn() -> (T, S)>
+}
+
+impl<'a, 'container, T, S> Iterator for SpecializedIter<'a, 'container, T, S> where
+    T : Clone + 'static,
+    S : Copy + 'static,
+    i64 : From<S>,
+    S : TryFrom<i64>
+{
+    type Item = ContainerResult<T>;
+
+    fn next(&mut self) -> Option<ContainerResult<T>> {
+        let specialization_value = self.specialization_values.next()?;
+        Some(specialization_from_value::<S>(specialization_value).and_then(|specialization| self.container.specialized(specialization)))
+    }
+}
+
 pub struct Container<'container> {
-    entries: RefCell<HashMap<TypeId, ContainerEntry>>,
-    specialized_entries: RefCell<HashMap<SpecializedEntryKey, ContainerEntry>>,
-    specializations: RefCell<HashMap<KnownSpecializationKey, HashSet<i32>>>,
-    spooky_ghost: PhantomData<&'container dyn Any>
+    entries: RefCell<EntryMap>,
+    specialized_entries: RefCell<Map<SpecializedEntryKey, ContainerEntry>>,
+    named_entries: RefCell<Map<NamedEntryKey, ContainerEntry>>,
+    tagged_entries: RefCell<Map<String, Vec<TaggedEntry>>>,
+    trait_witnesses: RefCell<Map<TypeId, Vec<TraitWitness>>>,
+    coercions: RefCell<Map<TypeId, Vec<Arc<dyn Any>>>>,
+    type_names: RefCell<Map<TypeId, &'static str>>,
+    type_aliases: RefCell<Map<TypeId, String>>,
+    requirements: RefCell<Map<TypeId, &'static str>>,
+    specializations: RefCell<Map<KnownSpecializationKey, Set<i64>>>,
+    specialization_provenance: RefCell<Map<SpecializedEntryKey, Box<dyn Any>>>,
+    resolved_factories: RefCell<Map<TypeId, AnyFactory>>,
+    cached_instances: RefCell<Map<TypeId, Arc<dyn Any>>>,
+    pinned_instances: RefCell<Vec<Arc<dyn Any>>>,
+    separate_cache: Cell<bool>,
+    pure_resolution: Cell<bool>,
+    fallback_resolver: RefCell<Option<TypeIdResolver>>,
+    pre_resolve_interceptor: RefCell<Option<TypeIdResolver>>,
+    on_missing: RefCell<Option<MissingHandler>>,
+    resolved_specialized_factories: RefCell<Map<SpecializedEntryKey, AnyFactory>>,
+    resolved_named_factories: RefCell<Map<NamedEntryKey, AnyFactory>>,
+    shadowed_entries: RefCell<Map<TypeId, Vec<ShadowedEntry>>>,
+    disabled_entries: RefCell<Set<TypeId>>,
+    transient_entries: RefCell<Set<TypeId>>,
+    resolution_counts: RefCell<Map<TypeId, usize>>,
+    scoped_factories: RefCell<Map<TypeId, AnyFactory>>,
+    disposers: RefCell<Vec<Disposer>>,
+    #[cfg(feature = "std")]
+    memoized_factories: RefCell<Map<TypeId, Rc<dyn Any>>>,
+    rc_entries: RefCell<Map<TypeId, Rc<dyn Any>>>,
+    sealed_entries: RefCell<Set<TypeId>>,
+    profiles: RefCell<Map<String, Vec<Container<'static>>>>,
+    resolution_stack: RefCell<Vec<&'static str>>,
+    specialization_stack: RefCell<Vec<(TypeId, i64)>>,
+    active_factories: RefCell<Vec<usize>>,
+    phase: Cell<Phase>,
+    resolution_budget: Cell<Option<usize>>,
+    factory_runs: Cell<usize>,
+    chain_depth: Cell<usize>,
+    chain_budget: Cell<Option<usize>>,
+    chain_depth_limit: Cell<Option<usize>>,
+    deepest_chain_depth: Cell<usize>,
+    total_factory_runs: Cell<usize>,
+    depth_limit: Cell<Option<usize>>,
+    max_depth: Cell<usize>,
+    strict_phases: Cell<bool>,
+    dependency_graph: RefCell<Option<DependencyGraph>>,
+    parent: Option<&'container Container<'container>>
+}
+
+impl<'container> Debug for Container<'container> {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        let mut registrations: Vec<String> = Vec::new();
+        for (type_id, container_entry) in self.entries.borrow().iter() {
+            let entry_kind = match container_entry {
+                ContainerEntry::Instance(_) if self.resolved_factories.borrow().contains_key(type_id) => "cached",
+                ContainerEntry::Instance(_) => "instance",
+                _ if self.cached_instances.borrow().contains_key(type_id) => "cached",
+                _ => "factory"
+            };
+            registrations.push(format!("{} (default, {})", self.type_name_of(type_id), entry_kind));
+        }
+        for (specialized_entry_key, container_entry) in self.specialized_entries.borrow().iter() {
+            let entry_kind = match container_entry {
+                ContainerEntry::Instance(_) if self.resolved_specialized_factories.borrow().contains_key(specialized_entry_key) => "cached",
+                ContainerEntry::Instance(_) => "instance",
+                _ => "factory"
+            };
+            registrations.push(format!("{} (specialized {}, {})", self.type_name_of(&specialized_entry_key.type_id),
+                specialized_entry_key.specialization_value, entry_kind));
+        }
+        for (named_entry_key, container_entry) in self.named_entries.borrow().iter() {
+            let entry_kind = match container_entry {
+                ContainerEntry::Instance(_) if self.resolved_named_factories.borrow().contains_key(named_entry_key) => "cached",
+                ContainerEntry::Instance(_) => "instance",
+                _ => "factory"
+            };
+            registrations.push(format!("{} (named {:?}, {})", self.type_name_of(&named_entry_key.0), named_entry_key.1, entry_kind));
+        }
+        for (tag, tagged_entries) in self.tagged_entries.borrow().iter() {
+            for tagged_entry in tagged_entries.iter() {
+                let entry_kind = if tagged_entry.instance.is_some() { "cached" } else { "factory" };
+                registrations.push(format!("{} (tagged {:?}, {})", self.type_name_of(&tagged_entry.type_id), tag, entry_kind));
+            }
+        }
+        registrations.sort();
+
+        f.debug_struct("Container")
+            .field("entries", &self.entries.borrow().len())
+            .field("specialized_entries", &self.specialized_entries.borrow().len())
+            .field("named_entries", &self.named_entries.borrow().len())
+            .field("tagged_entries", &self.tagged_entries.borrow().values().map(Vec::len).sum::<usize>())
+            .field("registrations", &registrations)
+            .finish()
+    }
+}
+
+impl<'container> Default for Container<'container> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'container> Drop for Container<'container> {
+    fn drop(&mut self) {
+        for disposer in mem::take(&mut *self.disposers.borrow_mut()).iter().rev() {
+            disposer();
+        }
+    }
 }
 
 impl<'container> Container<'container> {
     pub fn new() -> Self {
         Self {
-            entries: RefCell::new(HashMap::new()),
-            specialized_entries: RefCell::new(HashMap::new()),
-            specializations: RefCell::new(HashMap::new()),
-            spooky_ghost: PhantomData
+            entries: RefCell::new(EntryMap::default()),
+            specialized_entries: RefCell::new(Map::new()),
+            named_entries: RefCell::new(Map::new()),
+            tagged_entries: RefCell::new(Map::new()),
+            trait_witnesses: RefCell::new(Map::new()),
+            coercions: RefCell::new(Map::new()),
+            type_names: RefCell::new(Map::new()),
+            type_aliases: RefCell::new(Map::new()),
+            requirements: RefCell::new(Map::new()),
+            specializations: RefCell::new(Map::new()),
+            specialization_provenance: RefCell::new(Map::new()),
+            resolved_factories: RefCell::new(Map::new()),
+            cached_instances: RefCell::new(Map::new()),
+            pinned_instances: RefCell::new(Vec::new()),
+            separate_cache: Cell::new(false),
+            pure_resolution: Cell::new(false),
+            fallback_resolver: RefCell::new(None),
+            pre_resolve_interceptor: RefCell::new(None),
+            on_missing: RefCell::new(None),
+            resolved_specialized_factories: RefCell::new(Map::new()),
+            resolved_named_factories: RefCell::new(Map::new()),
+            shadowed_entries: RefCell::new(Map::new()),
+            disabled_entries: RefCell::new(Set::new()),
+            transient_entries: RefCell::new(Set::new()),
+            resolution_counts: RefCell::new(Map::new()),
+            scoped_factories: RefCell::new(Map::new()),
+            disposers: RefCell::new(Vec::new()),
+            #[cfg(feature = "std")]
+            memoized_factories: RefCell::new(Map::new()),
+            rc_entries: RefCell::new(Map::new()),
+            sealed_entries: RefCell::new(Set::new()),
+            profiles: RefCell::new(Map::new()),
+            resolution_stack: RefCell::new(Vec::new()),
+            specialization_stack: RefCell::new(Vec::new()),
+            active_factories: RefCell::new(Vec::new()),
+            phase: Cell::new(Phase::Registering),
+            resolution_budget: Cell::new(None),
+            factory_runs: Cell::new(0),
+            chain_depth: Cell::new(0),
+            chain_budget: Cell::new(None),
+            chain_depth_limit: Cell::new(None),
+            deepest_chain_depth: Cell::new(0),
+            total_factory_runs: Cell::new(0),
+            depth_limit: Cell::new(None),
+            max_depth: Cell::new(0),
+            strict_phases: Cell::new(false),
+            dependency_graph: RefCell::new(None),
+            parent: None
+        }
+    }
+
+    /// Builds a container whose default registrations are keyed with `hasher` instead of SipHash.
+    /// Only the default entry map uses it; it is the one every `default` call goes through.
+    #[cfg(feature = "std")]
+    pub fn with_hasher<S>(hasher: S) -> Self where
+        S : BuildHasher + 'static
+    {
+        let container = Self::new();
+        *container.entries.borrow_mut() = EntryMap::with_hasher(hasher);
+        container
+    }
+
+    #[cfg(feature = "std")]
+    pub fn with_capacity(entries: usize, specialized: usize) -> Self {
+        let container = Self::new();
+        *container.entries.borrow_mut() = EntryMap::with_capacity(entries);
+        *container.specialized_entries.borrow_mut() = Map::with_capacity(specialized);
+        container
+    }
+
+    /// Caches default singletons in a map of their own instead of swapping the factory entry for
+    /// its instance, so resolution never writes to the registration map.
+    pub fn cache_separately(&self) -> &Self {
+        self.separate_cache.set(true);
+        self
+    }
+
+    /// Consulted by `default` when neither this container nor its parents have an entry.  The value
+    /// returned must be the `T` being resolved, otherwise resolution fails with `DowncastFailure`.
+    pub fn set_fallback_resolver(&self, resolver: impl Fn(TypeId) -> Option<Arc<dyn Any>> + 'static) -> &Self {
+        *self.fallback_resolver.borrow_mut() = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Stops `default` from caching factory results, so resolving through `&self` never changes
+    /// what later resolutions see.  Singletons are cached only through `warmup` or `set_cached`.
+    pub fn pure_resolution(&self) -> &Self {
+        self.pure_resolution.set(true);
+        self
+    }
+
+    pub fn warmup<T>(&self) -> ContainerResult<&Self> where
+        T : Clone + 'static
+    {
+        let type_id = TypeId::of::<T>();
+        let entry = self.entries.borrow().get(&type_id).cloned();
+        match entry {
+            Some(ContainerEntry::Factory(factory)) => {
+                let new_instance = self.invoke_factory::<T>(&factory)?;
+                downcast_instance::<T>(&new_instance)?;
+                self.cache_default_instance::<T>(new_instance)?;
+                Ok(self)
+            },
+            Some(_) => Ok(self),
+            None => Err(self.missing_entry::<T>())
+        }
+    }
+
+    pub fn set_cached<T>(&self, instance: T) -> ContainerResult<&Self> where
+        T : Clone + 'static
+    {
+        self.cache_default_instance::<T>(Arc::new(instance))?;
+        Ok(self)
+    }
+
+    /// Consulted by `default` before any lookup, so a value it supplies wins over a registered
+    /// entry.  Supplied values are not cached; the interceptor is asked again on every resolution.
+    /// Every default resolution sees it, `resolve_into` and `default_cow` included, which stop
+    /// borrowing cached instances while one is set.  `borrow_instance` only reads what is stored,
+    /// and specialized and named resolution are not keyed by `TypeId` alone, so they bypass it.
+    pub fn set_pre_resolve_interceptor(&self, interceptor: impl Fn(TypeId) -> Option<Arc<dyn Any>> + 'static) -> &Self {
+        *self.pre_resolve_interceptor.borrow_mut() = Some(Arc::new(interceptor));
+        self
+    }
+
+    /// Called by `default` as a last resort when nothing supplies the type, giving it a chance to
+    /// register the entry on demand.  The lookup is retried once afterwards.
+    pub fn set_on_missing(&self, handler: impl Fn(&Container, TypeId) + 'static) -> &Self {
+        *self.on_missing.borrow_mut() = Some(Arc::new(handler));
+        self
+    }
+
+    /// Creates an empty container that falls back to `self` for default registrations it lacks.
+    /// Children can be nested to any depth; a parent can't be changed after the fact, so the chain
+    /// always ends at a root.
+    pub fn child(&'container self) -> Container<'container> {
+        let mut child = Self::new();
+        child.parent = Some(self);
+        child
+    }
+
+    pub fn root(&self) -> &Container<'container> {
+        let mut container = self;
+        while let Some(parent) = container.parent {
+            container = parent;
+        }
+        container
+    }
+
+    pub fn install(&self, modules: &[Box<dyn Module>]) -> &Self {
+        for module in modules.iter() {
+            module.register(self);
         }
+        self
     }
 
     pub fn register_instance<T>(&self, instance: T) -> &Self where
         T : Clone + 'static
     {
+        log_debug!("registering instance for {}", self.display_name::<T>());
+        self.remember_type_name::<T>();
         let type_id = TypeId::of::<T>();
         self.entries.borrow_mut().insert(type_id, ContainerEntry::Instance(Arc::new(instance)));
+        self.resolved_factories.borrow_mut().remove(&type_id);
+        self.cached_instances.borrow_mut().remove(&type_id);
         self
     }
 
+    pub fn register_arc<T>(&self, instance: Arc<T>) -> &Self where
+        T : ?Sized + 'static
+    {
+        self.register_instance(instance)
+    }
+
+    pub fn try_register_instance<T>(&self, instance: T) -> ContainerResult<&Self> where
+        T : Clone + 'static
+    {
+        let type_id = TypeId::of::<T>();
+        if self.resolved_factories.borrow().contains_key(&type_id) || self.cached_instances.borrow().contains_key(&type_id) {
+            return Err(ContainerError::AlreadyResolved);
+        }
+        Ok(self.register_instance(instance))
+    }
+
+    pub fn register_instance_try<T, Raw, F>(&self, raw: Raw, convert: F) -> &Self where
+        T : Clone + 'static,
+        Raw : Clone + 'static,
+        F : Fn(Raw) -> ContainerResult<T> + 'static
+    {
+        self.register_factory(move |_container: &Container| -> ContainerResult<T> { convert(raw.clone()) })
+    }
+
     pub fn register_specialized_instance<T, F, S>(&self, specialization: S, instance: T) -> &Self where
         T : Clone + 'static,
         S : Copy + 'static,
         F: Fn(&Container, S) -> T + 'static,
-        i32 : From<S>,
-        S : From<i32>
+        i64 : From<S>,
+        S : TryFrom<i64>
     {
         let specialized_entry_key = SpecializedEntryKey::new_for_specialization::<T, S>(specialization);
+        log_debug!("registering instance for {} specialized as {}", self.display_name::<T>(), specialized_entry_key.specialization_value);
         self.specialized_entries.borrow_mut().insert(specialized_entry_key, ContainerEntry::Instance(Arc::new(instance)));
+        self.resolved_specialized_factories.borrow_mut().remove(&specialized_entry_key);
         self.register_specialization::<T, S>(specialization);
         self
     }
 
+    /// Starts a fluent registration for `T`, committed on `build` or when the builder is dropped.
+    pub fn register<T>(&self) -> RegistrationBuilder<'_, 'container, T> where
+        T : Clone + 'static
+    {
+        RegistrationBuilder::new(self)
+    }
+
+    /// Factories are only ever invoked on the thread that owns the container, so they need not be
+    /// `Send` or `Sync` and may capture `Rc`, `RefCell` and similar single-threaded state.
     pub fn register_factory<T, F>(&self, factory: F) -> &Self where
         T : Clone + 'static,
         F : Fn(&Container) -> Result<T, ContainerError> + 'static
@@ -216,141 +957,1871 @@ impl<'container> Container<'container> {
                 Err(err) => Err(err)
             }
         };
+        log_debug!("registering factory for {}", self.display_name::<T>());
+        self.remember_type_name::<T>();
         self.entries.borrow_mut().insert(type_id, ContainerEntry::Factory(Arc::new(any_factory)));
+        self.resolved_factories.borrow_mut().remove(&type_id);
+        self.cached_instances.borrow_mut().remove(&type_id);
         self
     }
 
-    pub fn register_specialized_factory<T, S, F>(&self, specialization: S, factory: F) -> &Self where
+    // `register_autoN` registers a factory that resolves each of `build`'s N arguments with `default`
+    // and passes them in order, so constructors can be wired without a hand-written factory body.
+    register_auto!(register_auto1, D1);
+    register_auto!(register_auto2, D1, D2);
+    register_auto!(register_auto3, D1, D2, D3);
+    register_auto!(register_auto4, D1, D2, D3, D4);
+    register_auto!(register_auto5, D1, D2, D3, D4, D5);
+    register_auto!(register_auto6, D1, D2, D3, D4, D5, D6);
+
+    /// Registers `factory` as a singleton whose lifetime can be switched later through the handle,
+    /// e.g. from deployment config.  Switching to transient discards any cached instance.
+    pub fn register_factory_configurable<T, F>(&self, factory: F) -> LifetimeHandle<'_, 'container> where
         T : Clone + 'static,
-        S : Copy + 'static,
-        F: Fn(&Container) -> Result<T, ContainerError> + 'static,
-        i32 : From<S>,
-        S : From<i32>
+        F : Fn(&Container) -> Result<T, ContainerError> + 'static
+    {
+        self.register_factory(factory);
+        LifetimeHandle {
+            container: self,
+            type_id: TypeId::of::<T>()
+        }
+    }
+
+    /// Registers a singleton that is built once per scope.  Whichever container resolves it first,
+    /// this one or any child, caches its own instance and disposes of it when it is dropped.
+    pub fn register_scoped<T, F>(&self, factory: F) -> &Self where
+        T : Dispose + Clone + 'static,
+        F : Fn(&Container) -> Result<T, ContainerError> + 'static
     {
-        let specialized_entry_key = SpecializedEntryKey::new_for_specialization::<T, S>(specialization);
         let any_factory = move |container: &Container| -> ContainerResult<Arc<dyn Any>> {
-            match factory(container) {
-                Ok(new_instance) => Ok(Arc::new(new_instance)),
-                Err(err) => Err(err)
-            }
+            let new_instance = factory(container)?;
+            let disposable = new_instance.clone();
+            container.disposers.borrow_mut().push(Box::new(move || disposable.dispose()));
+            Ok(Arc::new(new_instance))
         };
-        self.specialized_entries.borrow_mut().insert(specialized_entry_key, ContainerEntry::SpecializedFactory(Arc::new(any_factory)));
-        self.register_specialization::<T, S>(specialization);
+        log_debug!("registering scoped factory for {}", self.display_name::<T>());
+        self.remember_type_name::<T>();
+        self.scoped_factories.borrow_mut().insert(TypeId::of::<T>(), Arc::new(any_factory));
         self
     }
 
-    pub fn default<T>(&self) -> ContainerResult<T> where
-        T : Clone + 'static
-    {
-        let type_id = TypeId::of::<T>();
-        let entry = {
-            self.entries.borrow().get(&type_id).cloned()
-        };
+    /// Stages the registrations made by `register` under `profile`.  Nothing is visible to
+    /// resolution until `activate_profile` applies them on top of the current registrations.
+    pub fn register_profile(&self, profile: &str, register: impl FnOnce(&Container)) -> &Self {
+        let staged = Container::new();
+        register(&staged);
+        self.profiles.borrow_mut().entry(profile.to_string()).or_default().push(staged);
+        self
+    }
 
-        match entry {
+    pub fn activate_profile(&self, profile: &str) -> bool {
+        let staged_containers = self.profiles.borrow_mut().remove(profile);
+        match staged_containers {
+            Some(staged_containers) => {
+                log_debug!("activating profile {}", profile);
+                for staged in staged_containers.iter() {
+                    self.apply_staged(staged);
+                }
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn push_override<T, F>(&self, factory: F) -> &Self where
+        T : Clone + 'static,
+        F : Fn(&Container) -> Result<T, ContainerError> + 'static
+    {
+        let type_id = TypeId::of::<T>();
+        let shadowed_entry = ShadowedEntry {
+            entry: self.entries.borrow().get(&type_id).cloned(),
+            resolved_factory: self.resolved_factories.borrow().get(&type_id).cloned()
+        };
+        self.shadowed_entries.borrow_mut().entry(type_id).or_default().push(shadowed_entry);
+        self.register_factory(factory)
+    }
+
+    pub fn pop_override<T>(&self) -> bool where
+        T : 'static
+    {
+        let type_id = TypeId::of::<T>();
+        let shadowed = match self.shadowed_entries.borrow_mut().get_mut(&type_id) {
+            Some(shadowed_entries) => shadowed_entries.pop(),
+            None => None
+        };
+        match shadowed {
+            Some(shadowed_entry) => {
+                match shadowed_entry.entry {
+                    Some(container_entry) => self.entries.borrow_mut().insert(type_id, container_entry),
+                    None => self.entries.borrow_mut().remove(&type_id)
+                };
+                match shadowed_entry.resolved_factory {
+                    Some(factory) => self.resolved_factories.borrow_mut().insert(type_id, factory),
+                    None => self.resolved_factories.borrow_mut().remove(&type_id)
+                };
+                self.cached_instances.borrow_mut().remove(&type_id);
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn register_factory_using_specialized<T, Dep, S, F>(&self, specialization: S, factory: F) -> &Self where
+        T : Clone + 'static,
+        Dep : Clone + 'static,
+        S : Copy + 'static,
+        F : Fn(&Container, Dep) -> Result<T, ContainerError> + 'static,
+        i64 : From<S>,
+        S : TryFrom<i64>
+    {
+        self.register_factory(move |container: &Container| -> ContainerResult<T> {
+            let dependency: Dep = container.specialized(specialization)?;
+            factory(container, dependency)
+        })
+    }
+
+    pub fn register_factory_with_init<T, F, I>(&self, factory: F, init: I) -> &Self where
+        T : Clone + 'static,
+        F : Fn(&Container) -> Result<T, ContainerError> + 'static,
+        I : Fn(&Container, &T) + 'static
+    {
+        self.register_factory(move |container: &Container| -> ContainerResult<T> {
+            let new_instance = factory(container)?;
+            init(container, &new_instance);
+            Ok(new_instance)
+        })
+    }
+
+    pub fn register_factory_named_type<T, F>(&self, display_name: &str, factory: F) -> &Self where
+        T : Clone + 'static,
+        F : Fn(&Container) -> Result<T, ContainerError> + 'static
+    {
+        self.type_aliases.borrow_mut().insert(TypeId::of::<T>(), display_name.to_string());
+        self.register_factory(factory)
+    }
+
+    /// Registers the factory only if `Prereq` is registered at the time of this call.  Registering
+    /// `Prereq` afterwards does not retroactively register the factory.
+    pub fn register_factory_when_present<Prereq, T, F>(&self, factory: F) -> &Self where
+        Prereq : 'static,
+        T : Clone + 'static,
+        F : Fn(&Container) -> Result<T, ContainerError> + 'static
+    {
+        if self.contains::<Prereq>() {
+            self.register_factory(factory);
+        }
+        self
+    }
+
+    pub fn register_factory_with_retry<T, F>(&self, attempts: usize, factory: F) -> &Self where
+        T : Clone + 'static,
+        F : Fn(&Container) -> Result<T, ContainerError> + 'static
+    {
+        self.register_retrying_factory(attempts, factory, || { })
+    }
+
+    #[cfg(feature = "std")]
+    pub fn register_factory_with_retry_backoff<T, F>(&self, attempts: usize, backoff: Duration, factory: F) -> &Self where
+        T : Clone + 'static,
+        F : Fn(&Container) -> Result<T, ContainerError> + 'static
+    {
+        self.register_retrying_factory(attempts, factory, move || thread::sleep(backoff))
+    }
+
+    fn register_retrying_factory<T, F, B>(&self, attempts: usize, factory: F, between_attempts: B) -> &Self where
+        T : Clone + 'static,
+        F : Fn(&Container) -> Result<T, ContainerError> + 'static,
+        B : Fn() + 'static
+    {
+        let attempts = attempts.max(1);
+        self.register_factory(move |container: &Container| -> ContainerResult<T> {
+            let mut attempt = 1;
+            loop {
+                match factory(container) {
+                    Ok(new_instance) => return Ok(new_instance),
+                    Err(err) if attempt >= attempts => return Err(err),
+                    Err(_) => {
+                        attempt += 1;
+                        between_attempts();
+                    }
+                }
+            }
+        })
+    }
+
+    /// Registers `Arc<Dyn>` from a factory producing the concrete value.  Rust can't unsize to an
+    /// arbitrary `Dyn` generically, so the coercion is passed in; `|value| value` is enough.
+    pub fn register_dyn_factory<Dyn, Concrete, F>(&self, factory: F, coerce: impl Fn(Arc<Concrete>) -> Arc<Dyn> + 'static) -> &Self where
+        Dyn : ?Sized + 'static,
+        Concrete : 'static,
+        F : Fn(&Container) -> Result<Concrete, ContainerError> + 'static
+    {
+        self.register_factory(move |container: &Container| -> ContainerResult<Arc<Dyn>> {
+            Ok(coerce(Arc::new(factory(container)?)))
+        })
+    }
+
+    pub fn register_shared_as<Concrete, Dyn>(&self, coerce: impl Fn(Arc<Concrete>) -> Arc<Dyn> + 'static) -> &Self where
+        Concrete : 'static,
+        Dyn : ?Sized + 'static
+    {
+        self.register_factory(move |container: &Container| -> ContainerResult<Arc<Dyn>> {
+            let concrete: Arc<Concrete> = container.default()?;
+            Ok(coerce(concrete))
+        })
+    }
+
+    /// Registers `Arc<Dyn>` so that `resolve_concrete` always refuses to recover the value behind
+    /// it, even if the concrete `Arc` is registered separately.  The seal stays on the type.
+    pub fn register_sealed_factory<Dyn, F>(&self, factory: F) -> &Self where
+        Dyn : ?Sized + 'static,
+        F : Fn(&Container) -> Result<Arc<Dyn>, ContainerError> + 'static
+    {
+        self.sealed_entries.borrow_mut().insert(TypeId::of::<Arc<Dyn>>());
+        self.register_factory(factory)
+    }
+
+    pub fn register_fn<A, R>(&self, function: impl Fn(A) -> R + Send + Sync + 'static) -> &Self where
+        A : 'static,
+        R : 'static
+    {
+        self.register_instance::<SharedFn<A, R>>(Arc::new(function))
+    }
+
+    /// Same as `register_shared_as`, named for callers thinking in terms of linking a concrete
+    /// singleton to the trait it implements.
+    pub fn link_concrete_to_trait<Concrete, Dyn>(&self, coerce: impl Fn(Arc<Concrete>) -> Arc<Dyn> + 'static) -> &Self where
+        Concrete : 'static,
+        Dyn : ?Sized + 'static
+    {
+        self.register_shared_as(coerce)
+    }
+
+    /// Records that `Concrete` implements `Dyn` without registering `Arc<Dyn>`.  Resolving `Arc<Dyn>`
+    /// while `Arc<Concrete>` is registered then fails with `NotRegisteredAsTrait` naming it.
+    pub fn witness_trait<Concrete, Dyn>(&self, _coerce: fn(Arc<Concrete>) -> Arc<Dyn>) -> &Self where
+        Concrete : 'static,
+        Dyn : ?Sized + 'static
+    {
+        self.record_trait_witness::<Concrete, Dyn>();
+        self
+    }
+
+    pub fn register_coercion<Concrete, Dyn>(&self, coerce: impl Fn(Arc<Concrete>) -> Arc<Dyn> + 'static) -> &Self where
+        Concrete : 'static,
+        Dyn : ?Sized + 'static
+    {
+        self.record_trait_witness::<Concrete, Dyn>();
+        self.remember_type_name::<Arc<Dyn>>();
+        let coercion: Resolver<Arc<Dyn>> = Box::new(move |container: &Container| -> ContainerResult<Arc<Dyn>> {
+            let concrete: Arc<Concrete> = container.default()?;
+            Ok(coerce(concrete))
+        });
+        self.coercions.borrow_mut().entry(TypeId::of::<Arc<Dyn>>()).or_default().push(Arc::new(coercion));
+        self
+    }
+
+    pub fn register_rc<T>(&self, value: Rc<T>) -> &Self where
+        T : 'static
+    {
+        self.remember_type_name::<T>();
+        self.rc_entries.borrow_mut().insert(TypeId::of::<T>(), value);
+        self
+    }
+
+    pub fn register_named_instance<T>(&self, name: &str, instance: T) -> &Self where
+        T : Clone + 'static
+    {
+        let named_entry_key = (TypeId::of::<T>(), name.to_string());
+        self.remember_type_name::<T>();
+        self.resolved_named_factories.borrow_mut().remove(&named_entry_key);
+        self.named_entries.borrow_mut().insert(named_entry_key, ContainerEntry::Instance(Arc::new(instance)));
+        self
+    }
+
+    pub fn register_named_factory<T, F>(&self, name: &str, factory: F) -> &Self where
+        T : Clone + 'static,
+        F : Fn(&Container) -> Result<T, ContainerError> + 'static
+    {
+        let named_entry_key = (TypeId::of::<T>(), name.to_string());
+        let any_factory = move |container: &Container| -> ContainerResult<Arc<dyn Any>> {
+            match factory(container) {
+                Ok(new_instance) => Ok(Arc::new(new_instance)),
+                Err(err) => Err(err)
+            }
+        };
+        self.remember_type_name::<T>();
+        self.resolved_named_factories.borrow_mut().remove(&named_entry_key);
+        self.named_entries.borrow_mut().insert(named_entry_key, ContainerEntry::Factory(Arc::new(any_factory)));
+        self
+    }
+
+    pub fn register_tagged<T, F>(&self, tag: &str, factory: F) -> &Self where
+        T : Clone + 'static,
+        F : Fn(&Container) -> Result<T, ContainerError> + 'static
+    {
+        let any_factory = move |container: &Container| -> ContainerResult<Arc<dyn Any>> {
+            match factory(container) {
+                Ok(new_instance) => Ok(Arc::new(new_instance)),
+                Err(err) => Err(err)
+            }
+        };
+        self.remember_type_name::<T>();
+        self.tagged_entries.borrow_mut().entry(tag.to_string()).or_default().push(TaggedEntry {
+            type_id: TypeId::of::<T>(),
+            factory: Arc::new(any_factory),
+            instance: None
+        });
+        self
+    }
+
+    pub fn register_specialized_factory<T, S, F>(&self, specialization: S, factory: F) -> &Self where
+        T : Clone + 'static,
+        S : Copy + 'static,
+        F: Fn(&Container) -> Result<T, ContainerError> + 'static,
+        i64 : From<S>,
+        S : TryFrom<i64>
+    {
+        let specialized_entry_key = SpecializedEntryKey::new_for_specialization::<T, S>(specialization);
+        let any_factory = move |container: &Container| -> ContainerResult<Arc<dyn Any>> {
+            match factory(container) {
+                Ok(new_instance) => Ok(Arc::new(new_instance)),
+                Err(err) => Err(err)
+            }
+        };
+        log_debug!("registering factory for {} specialized as {}", self.display_name::<T>(), specialized_entry_key.specialization_value);
+        self.specialized_entries.borrow_mut().insert(specialized_entry_key, ContainerEntry::SpecializedFactory(Arc::new(any_factory)));
+        self.resolved_specialized_factories.borrow_mut().remove(&specialized_entry_key);
+        self.register_specialization::<T, S>(specialization);
+        self
+    }
+
+    pub fn rebind_specialized<T, S, F>(&self, specialization: S, factory: F) -> bool where
+        T : Clone + 'static,
+        S : Copy + 'static,
+        F: Fn(&Container) -> Result<T, ContainerError> + 'static,
+        i64 : From<S>,
+        S : TryFrom<i64>
+    {
+        let specialized_entry_key = SpecializedEntryKey::new_for_specialization::<T, S>(specialization);
+        let replaced = self.specialized_entries.borrow().contains_key(&specialized_entry_key);
+        self.register_specialized_factory(specialization, factory);
+        replaced
+    }
+
+    pub fn register_specialized_factory_i32<T, F>(&self, key: i32, factory: F) -> &Self where
+        T : Clone + 'static,
+        F: Fn(&Container) -> Result<T, ContainerError> + 'static
+    {
+        self.register_specialized_factory::<T, i64, F>(i64::from(key), factory)
+    }
+
+    pub fn try_register_specialized_factory<T, S, F>(&self, specialization: S, factory: F) -> ContainerResult<&Self> where
+        T : Clone + 'static,
+        S : Copy + PartialEq + 'static,
+        F: Fn(&Container) -> Result<T, ContainerError> + 'static,
+        i64 : From<S>,
+        S : TryFrom<i64>
+    {
+        let specialized_entry_key = SpecializedEntryKey::new_for_specialization::<T, S>(specialization);
+        let existing_specialization = {
+            self.specialization_provenance.borrow().get(&specialized_entry_key)
+                .and_then(|existing| existing.downcast_ref::<S>().copied())
+        };
+        match existing_specialization {
+            Some(existing) if existing != specialization => {
+                Err(ContainerError::SpecializationCollision { specialization_value: specialized_entry_key.specialization_value })
+            },
+            _ => Ok(self.register_specialized_factory(specialization, factory))
+        }
+    }
+
+    /// Copies the registration for `T` from `source`.  Entries share their underlying `Arc`, so a
+    /// singleton that `source` has already resolved is shared with this container, not rebuilt.
+    pub fn copy_entry_from<T>(&self, source: &Container) -> bool where
+        T : 'static
+    {
+        let type_id = TypeId::of::<T>();
+        let entry = {
+            source.entries.borrow().get(&type_id).cloned()
+        };
+        match entry {
+            Some(container_entry) => {
+                self.remember_type_name::<T>();
+                self.entries.borrow_mut().insert(type_id, container_entry);
+                match source.resolved_factories.borrow().get(&type_id) {
+                    Some(factory) => self.resolved_factories.borrow_mut().insert(type_id, factory.clone()),
+                    None => self.resolved_factories.borrow_mut().remove(&type_id)
+                };
+                self.cached_instances.borrow_mut().remove(&type_id);
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn copy_specialized_entry_from<T, S>(&self, source: &Container, specialization: S) -> bool where
+        T : Clone + 'static,
+        S : Copy + 'static,
+        i64 : From<S>,
+        S : TryFrom<i64>
+    {
+        let specialized_entry_key = SpecializedEntryKey::new_for_specialization::<T, S>(specialization);
+        let specialized_entry = {
+            source.specialized_entries.borrow().get(&specialized_entry_key).cloned()
+        };
+        match specialized_entry {
+            Some(container_entry) => {
+                self.specialized_entries.borrow_mut().insert(specialized_entry_key, container_entry);
+                match source.resolved_specialized_factories.borrow().get(&specialized_entry_key) {
+                    Some(factory) => self.resolved_specialized_factories.borrow_mut().insert(specialized_entry_key, factory.clone()),
+                    None => self.resolved_specialized_factories.borrow_mut().remove(&specialized_entry_key)
+                };
+                self.register_specialization::<T, S>(specialization);
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn reset_cache(&self) {
+        self.cached_instances.borrow_mut().clear();
+        for (type_id, factory) in mem::take(&mut *self.resolved_factories.borrow_mut()) {
+            self.entries.borrow_mut().insert(type_id, ContainerEntry::Factory(factory));
+        }
+        for (specialized_entry_key, factory) in mem::take(&mut *self.resolved_specialized_factories.borrow_mut()) {
+            self.specialized_entries.borrow_mut().insert(specialized_entry_key, ContainerEntry::SpecializedFactory(factory));
+        }
+        for (named_entry_key, factory) in mem::take(&mut *self.resolved_named_factories.borrow_mut()) {
+            self.named_entries.borrow_mut().insert(named_entry_key, ContainerEntry::Factory(factory));
+        }
+        for tagged_entries in self.tagged_entries.borrow_mut().values_mut() {
+            for tagged_entry in tagged_entries.iter_mut() {
+                tagged_entry.instance = None;
+            }
+        }
+    }
+
+    /// Number of default singletons currently holding a cached instance.
+    pub fn cached_count(&self) -> usize {
+        self.resolved_factories.borrow().len() + self.cached_instances.borrow().len()
+    }
+
+    /// Reverts cached default singletons to their factories when `predicate` accepts the type name.
+    /// Instances registered directly have no factory to revert to and are left alone.
+    pub fn evict_if(&self, predicate: impl Fn(&'static str) -> bool) {
+        let type_names = self.type_names.borrow().clone();
+        let matches = |type_id: &TypeId| type_names.get(type_id).is_some_and(|type_name| predicate(type_name));
+
+        let evicted: Vec<(TypeId, AnyFactory)> = self.resolved_factories.borrow()
+            .iter()
+            .filter(|(type_id, _)| matches(type_id))
+            .map(|(type_id, factory)| (*type_id, factory.clone()))
+            .collect();
+        for (type_id, factory) in evicted {
+            log_debug!("evicting cached {}", self.type_name_of(&type_id));
+            self.resolved_factories.borrow_mut().remove(&type_id);
+            self.entries.borrow_mut().insert(type_id, ContainerEntry::Factory(factory));
+        }
+        self.cached_instances.borrow_mut().retain(|type_id, _| !matches(type_id));
+    }
+
+    pub fn require<T>(&self) -> &Self where
+        T : 'static
+    {
+        self.requirements.borrow_mut().insert(TypeId::of::<T>(), type_name::<T>());
+        self
+    }
+
+    /// Checks every type declared with `require` against the same registrations resolution would
+    /// find: default, specialized, named, scoped and `Rc` entries, here or in a parent.
+    pub fn validate(&self) -> ContainerResult<()> {
+        let type_names = self.unsatisfied_requirements();
+        if type_names.is_empty() {
+            return Ok(());
+        }
+        Err(ContainerError::UnsatisfiedRequirements { type_names })
+    }
+
+    pub(crate) fn unsatisfied_requirements(&self) -> Vec<String> {
+        let mut type_names: Vec<String> = Vec::new();
+        for (type_id, type_name) in self.requirements.borrow().iter() {
+            if !self.is_registered_in_chain(*type_id) {
+                match self.type_aliases.borrow().get(type_id) {
+                    Some(display_name) => type_names.push(display_name.clone()),
+                    None => type_names.push(type_name.to_string())
+                }
+            }
+        }
+        type_names.sort();
+        type_names
+    }
+
+    fn is_registered_in_chain(&self, type_id: TypeId) -> bool {
+        let mut container = Some(self);
+        while let Some(scope) = container {
+            let registered = (scope.entries.borrow().contains_key(&type_id) && !scope.is_disabled(&type_id)) ||
+                scope.has_specializations(type_id) ||
+                scope.named_entries.borrow().keys().any(|named_entry_key| named_entry_key.0 == type_id) ||
+                scope.scoped_factories.borrow().contains_key(&type_id) ||
+                scope.rc_entries.borrow().contains_key(&type_id);
+            if registered {
+                return true;
+            }
+            container = scope.parent;
+        }
+        false
+    }
+
+    pub fn export_metadata(&self) -> ContainerMetadata {
+        let mut entries = Vec::new();
+        for (type_id, container_entry) in self.entries.borrow().iter() {
+            entries.push(EntryMetadata {
+                type_name: self.type_name_of(type_id),
+                kind: EntryKind::Default,
+                specialization_type_name: None,
+                specialization_value: None,
+                name: None,
+                resolved: matches!(container_entry, ContainerEntry::Instance(_))
+            });
+        }
+        for (specialized_entry_key, container_entry) in self.specialized_entries.borrow().iter() {
+            entries.push(EntryMetadata {
+                type_name: self.type_name_of(&specialized_entry_key.type_id),
+                kind: EntryKind::Specialized,
+                specialization_type_name: Some(self.type_name_of(&specialized_entry_key.specialization_type_id)),
+                specialization_value: Some(specialized_entry_key.specialization_value),
+                name: None,
+                resolved: matches!(container_entry, ContainerEntry::Instance(_))
+            });
+        }
+        for ((type_id, name), container_entry) in self.named_entries.borrow().iter() {
+            entries.push(EntryMetadata {
+                type_name: self.type_name_of(type_id),
+                kind: EntryKind::Named,
+                specialization_type_name: None,
+                specialization_value: None,
+                name: Some(name.clone()),
+                resolved: matches!(container_entry, ContainerEntry::Instance(_))
+            });
+        }
+        entries.sort_by(|a, b| {
+            (&a.type_name, a.kind, &a.specialization_type_name, a.specialization_value, &a.name)
+                .cmp(&(&b.type_name, b.kind, &b.specialization_type_name, b.specialization_value, &b.name))
+        });
+        ContainerMetadata { entries }
+    }
+
+    pub fn entry_kinds(&self) -> Vec<(String, EntryState)> {
+        let mut entry_kinds = Vec::new();
+        for (type_id, container_entry) in self.entries.borrow().iter() {
+            let entry_state = match container_entry {
+                _ if self.is_disabled(type_id) => EntryState::Disabled,
+                _ if self.cached_instances.borrow().contains_key(type_id) => EntryState::Cached,
+                ContainerEntry::Factory(_) if self.pure_resolution.get() || self.transient_entries.borrow().contains(type_id) => EntryState::Transient,
+                _ => container_entry.state(self.resolved_factories.borrow().contains_key(type_id))
+            };
+            entry_kinds.push((self.type_name_of(type_id), entry_state));
+        }
+        for type_id in self.scoped_factories.borrow().keys() {
+            if !self.entries.borrow().contains_key(type_id) {
+                entry_kinds.push((self.type_name_of(type_id), EntryState::Scoped));
+            }
+        }
+        for type_id in self.rc_entries.borrow().keys() {
+            entry_kinds.push((self.type_name_of(type_id), EntryState::Rc));
+        }
+        #[cfg(feature = "std")]
+        for type_id in self.memoized_factories.borrow().keys() {
+            entry_kinds.push((self.type_name_of(type_id), EntryState::Memoized));
+        }
+        for (type_id, coercions) in self.coercions.borrow().iter() {
+            for _ in coercions.iter() {
+                entry_kinds.push((self.type_name_of(type_id), EntryState::Coercion));
+            }
+        }
+        for (specialized_entry_key, container_entry) in self.specialized_entries.borrow().iter() {
+            let resolved_from_factory = self.resolved_specialized_factories.borrow().contains_key(specialized_entry_key);
+            entry_kinds.push((self.type_name_of(&specialized_entry_key.type_id), container_entry.state(resolved_from_factory)));
+        }
+        for (named_entry_key, container_entry) in self.named_entries.borrow().iter() {
+            let resolved_from_factory = self.resolved_named_factories.borrow().contains_key(named_entry_key);
+            entry_kinds.push((self.type_name_of(&named_entry_key.0), container_entry.state(resolved_from_factory)));
+        }
+        for tagged_entries in self.tagged_entries.borrow().values() {
+            for tagged_entry in tagged_entries.iter() {
+                let entry_state = if tagged_entry.instance.is_some() { EntryState::Cached } else { EntryState::Factory };
+                entry_kinds.push((self.type_name_of(&tagged_entry.type_id), entry_state));
+            }
+        }
+        entry_kinds.sort();
+        entry_kinds
+    }
+
+    pub fn all_specialization_keys(&self) -> Vec<(String, String, Vec<i64>)> {
+        let mut specialization_keys: Vec<(String, String, Vec<i64>)> = self.specializations.borrow().iter()
+            .filter(|(_, specialization_values)| !specialization_values.is_empty())
+            .map(|(known_specialization_key, specialization_values)| {
+                let mut values: Vec<i64> = specialization_values.iter().copied().collect();
+                values.sort_unstable();
+                (self.type_name_of(&known_specialization_key.type_id), self.type_name_of(&known_specialization_key.specialization_type_id), values)
+            })
+            .collect();
+        specialization_keys.sort();
+        specialization_keys
+    }
+
+    /// The factory behind a default registration, including one whose singleton is already cached.
+    /// Plain instance registrations have none.
+    pub fn factory_for<T>(&self) -> Option<AnyFactory> where
+        T : 'static
+    {
+        let type_id = TypeId::of::<T>();
+        match self.entries.borrow().get(&type_id) {
+            Some(ContainerEntry::Factory(factory)) => Some(factory.clone()),
+            Some(_) => self.resolved_factories.borrow().get(&type_id).cloned(),
+            None => None
+        }
+    }
+
+    /// Whether both containers register the same default types and specialization keys.
+    /// Instances and factories themselves aren't compared.
+    pub fn same_shape(&self, other: &Container) -> bool {
+        let entries: Set<TypeId> = self.entries.borrow().keys().copied().collect();
+        let other_entries: Set<TypeId> = other.entries.borrow().keys().copied().collect();
+        let specialized_entries: Set<SpecializedEntryKey> = self.specialized_entries.borrow().keys().copied().collect();
+        let other_specialized_entries: Set<SpecializedEntryKey> = other.specialized_entries.borrow().keys().copied().collect();
+        entries == other_entries && specialized_entries == other_specialized_entries
+    }
+
+    pub fn lifetime_of<T>(&self) -> Option<Lifetime> where
+        T : 'static
+    {
+        self.lifetime_of_type(TypeId::of::<T>())
+    }
+
+    pub fn resolution_count<T>(&self) -> usize where
+        T : 'static
+    {
+        self.resolution_counts.borrow().get(&TypeId::of::<T>()).copied().unwrap_or(0)
+    }
+
+    /// Type names of default and specialized registrations that were never resolved, sorted.
+    pub fn unused_registrations(&self) -> Vec<&'static str> {
+        let mut type_ids: Vec<TypeId> = self.entries.borrow().keys().copied().collect();
+        type_ids.extend(self.specialized_entries.borrow().keys().map(|specialized_entry_key| specialized_entry_key.type_id));
+        let resolution_counts = self.resolution_counts.borrow();
+        let type_names = self.type_names.borrow();
+        let mut unused: Vec<&'static str> = type_ids.iter()
+            .filter(|type_id| resolution_counts.get(type_id).copied().unwrap_or(0) == 0)
+            .map(|type_id| type_names.get(type_id).copied().unwrap_or("<unknown>"))
+            .collect();
+        unused.sort_unstable();
+        unused.dedup();
+        unused
+    }
+
+    /// Removes the default registration for `T` and hands back its instance.  Fails with
+    /// `MissingEntry` when there is no instance to take, including a factory not yet resolved.
+    pub fn take_instance<T>(&self) -> ContainerResult<Arc<T>> where
+        T : 'static
+    {
+        let type_id = TypeId::of::<T>();
+        if self.is_disabled(&type_id) {
+            return Err(self.missing_entry::<T>());
+        }
+        let instance = match self.entries.borrow().get(&type_id) {
+            Some(ContainerEntry::Instance(instance)) => instance.clone(),
+            Some(ContainerEntry::Factory(_)) => match self.cached_instances.borrow().get(&type_id) {
+                Some(instance) => instance.clone(),
+                None => return Err(self.missing_entry::<T>())
+            },
+            _ => return Err(self.missing_entry::<T>())
+        };
+        if !instance.is::<T>() {
+            return Err(ContainerError::DowncastFailure);
+        }
+        // SAFETY: `Arc::downcast` needs `Send + Sync`, which stored instances don't have.  The
+        // concrete type was just checked, and this is the same pointer cast it performs.
+        let instance = unsafe { Arc::from_raw(Arc::into_raw(instance) as *const T) };
+        self.entries.borrow_mut().remove(&type_id);
+        self.resolved_factories.borrow_mut().remove(&type_id);
+        self.cached_instances.borrow_mut().remove(&type_id);
+        Ok(instance)
+    }
+
+    /// Counts the strong references to the `Arc<T>` built for `T`'s default registration, the
+    /// container's own included, so every clone handed out by `default` shows up.  `None` until the
+    /// registration has been resolved.
+    pub fn instance_strong_count<T>(&self) -> Option<usize> where
+        T : ?Sized + 'static
+    {
+        let type_id = TypeId::of::<Arc<T>>();
+        if self.is_disabled(&type_id) {
+            return None;
+        }
+        let instance = match self.entries.borrow().get(&type_id) {
+            Some(ContainerEntry::Instance(instance)) => instance.clone(),
+            Some(ContainerEntry::Factory(_)) => self.cached_instances.borrow().get(&type_id).cloned()?,
+            _ => return None
+        };
+        instance.downcast_ref::<Arc<T>>().map(Arc::strong_count)
+    }
+
+    pub fn display_name<T>(&self) -> String where
+        T : 'static
+    {
+        self.display_name_for(&TypeId::of::<T>(), type_name::<T>())
+    }
+
+    fn missing_entry<T>(&self) -> ContainerError where
+        T : 'static
+    {
+        ContainerError::MissingEntry { type_name: self.display_name::<T>() }
+    }
+
+    fn display_name_for(&self, type_id: &TypeId, type_name: &'static str) -> String {
+        match self.type_aliases.borrow().get(type_id) {
+            Some(display_name) => display_name.clone(),
+            None => type_name.to_string()
+        }
+    }
+
+    pub fn disable<T>(&self) -> bool where
+        T : 'static
+    {
+        let type_id = TypeId::of::<T>();
+        self.entries.borrow().contains_key(&type_id) && self.disabled_entries.borrow_mut().insert(type_id)
+    }
+
+    pub fn enable<T>(&self) -> bool where
+        T : 'static
+    {
+        self.disabled_entries.borrow_mut().remove(&TypeId::of::<T>())
+    }
+
+    fn is_disabled(&self, type_id: &TypeId) -> bool {
+        self.disabled_entries.borrow().contains(type_id)
+    }
+
+    /// Whether `T` has a default registration.  A disabled registration counts as absent.
+    pub fn contains<T>(&self) -> bool where
+        T : 'static
+    {
+        let type_id = TypeId::of::<T>();
+        self.entries.borrow().contains_key(&type_id) && !self.is_disabled(&type_id)
+    }
+
+    pub fn is_resolved<T>(&self) -> bool where
+        T : 'static
+    {
+        let type_id = TypeId::of::<T>();
+        !self.is_disabled(&type_id) &&
+            (matches!(self.entries.borrow().get(&type_id), Some(ContainerEntry::Instance(_))) || self.cached_instances.borrow().contains_key(&type_id))
+    }
+
+    pub fn default<T>(&self) -> ContainerResult<T> where
+        T : Clone + 'static
+    {
+        let instance = self.resolve_default_entry(TypeId::of::<T>(), type_name::<T>())?;
+        downcast_instance::<T>(&instance)
+    }
+
+    /// Type-erased counterpart of `default`, for helpers that only have a `TypeId` in hand.
+    pub fn resolve_any(&self, type_id: TypeId) -> ContainerResult<Arc<dyn Any>> {
+        let type_name = self.type_names.borrow().get(&type_id).copied().unwrap_or("<unknown>");
+        self.resolve_default_entry(type_id, type_name)
+    }
+
+    /// Same as `resolve_any`, also returning the type name captured at registration so a dynamic
+    /// layer can label the value it holds.
+    pub fn resolve_any_named(&self, type_id: TypeId) -> ContainerResult<(Arc<dyn Any>, &'static str)> {
+        let type_name = self.type_names.borrow().get(&type_id).copied().unwrap_or("<unknown>");
+        let instance = self.resolve_default_entry(type_id, type_name)?;
+        Ok((instance, type_name))
+    }
+
+    pub fn default_as<T, E>(&self) -> Result<T, E> where
+        T : Clone + 'static,
+        E : From<ContainerError>
+    {
+        self.default().map_err(E::from)
+    }
+
+    pub fn lazy<T>(&self) -> Lazy<'_, 'container, T> where
+        T : Clone + 'static
+    {
+        Lazy {
+            container: self,
+            value: OnceCell::new()
+        }
+    }
+
+    /// Resolves specializations one at a time as the iterator advances.  The set of values is
+    /// captured up front and no borrow is held between items, so the caller may resolve freely.
+    pub fn iter_specialized<T, S>(&self) -> SpecializedIter<'_, 'container, T, S> where
+        T : Clone + 'static,
+        S : Copy + 'static,
+        i64 : From<S>,
+        S : TryFrom<i64>
+    {
+        SpecializedIter {
+            container: self,
+            specialization_values: self.known_specialization_values::<T, S>().into_iter(),
+            spooky_ghost: PhantomData
+        }
+    }
+
+    pub fn resolve_fn<A, R>(&self) -> ContainerResult<SharedFn<A, R>> where
+        A : 'static,
+        R : 'static
+    {
+        self.default()
+    }
+
+    /// Resolves the `Arc<T>` singleton and returns a non-owning handle to it, which stops upgrading
+    /// once the container and every other owner have let go of it.
+    pub fn default_weak<T>(&self) -> ContainerResult<Weak<T>> where
+        T : ?Sized + 'static
+    {
+        let shared: Arc<T> = self.default()?;
+        Ok(Arc::downgrade(&shared))
+    }
+
+    pub fn resolve_rc<T>(&self) -> ContainerResult<Rc<T>> where
+        T : 'static
+    {
+        self.check_phase()?;
+        let rc_entry = self.rc_entries.borrow().get(&TypeId::of::<T>()).cloned();
+        match rc_entry {
+            Some(value) => value.downcast::<T>().map_err(|_| ContainerError::DowncastFailure),
+            None => Err(self.missing_entry::<T>())
+        }
+    }
+
+    pub fn resolve_shared<T>(&self) -> ContainerResult<Arc<T>> where
+        T : ?Sized + 'static
+    {
+        self.default()
+    }
+
+    /// Recovers the concrete `Arc` behind an `Arc<Dyn>` registration. Trait objects can't be
+    /// downcast generically, so this only succeeds when `Arc<Concrete>` is also registered and
+    /// both resolve to the same allocation, as with `register_shared_as`.
+    pub fn resolve_concrete<Dyn, Concrete>(&self) -> ContainerResult<Arc<Concrete>> where
+        Dyn : ?Sized + 'static,
+        Concrete : 'static
+    {
+        let shared: Arc<Dyn> = self.default()?;
+        if self.sealed_entries.borrow().contains(&TypeId::of::<Arc<Dyn>>()) {
+            log_debug!("{} is sealed", self.display_name::<Arc<Dyn>>());
+            return Err(ContainerError::DowncastFailure);
+        }
+        let concrete: Arc<Concrete> = match self.default() {
+            Ok(concrete) => concrete,
+            Err(err) if err.is_missing() => return Err(ContainerError::DowncastFailure),
+            Err(err) => return Err(err)
+        };
+        if Arc::as_ptr(&shared) as *const () == Arc::as_ptr(&concrete) as *const () {
+            Ok(concrete)
+        } else {
+            Err(ContainerError::DowncastFailure)
+        }
+    }
+
+    pub fn default_tracked<T>(&self) -> ContainerResult<(T, bool)> where
+        T : Clone + 'static
+    {
+        let factory_runs = self.total_factory_runs_in_chain();
+        let instance = self.default()?;
+        Ok((instance, self.total_factory_runs_in_chain() != factory_runs))
+    }
+
+    // The container and its parents, nearest first.
+    fn chain(&self) -> impl Iterator<Item = &Container<'container>> {
+        iter::successors(Some(self), |container| container.parent)
+    }
+
+    // A factory may run in a parent, so the runs of every container up the chain are counted.
+    fn total_factory_runs_in_chain(&self) -> usize {
+        let mut factory_runs = self.total_factory_runs.get();
+        let mut ancestor = self.parent;
+        while let Some(container) = ancestor {
+            factory_runs += container.total_factory_runs.get();
+            ancestor = container.parent;
+        }
+        factory_runs
+    }
+
+    #[cfg(feature = "std")]
+    pub fn default_timed<T>(&self) -> ContainerResult<(T, Duration)> where
+        T : Clone + 'static
+    {
+        let started = Instant::now();
+        let (instance, freshly_built) = self.default_tracked()?;
+        let elapsed = if freshly_built { started.elapsed() } else { Duration::ZERO };
+        Ok((instance, elapsed))
+    }
+
+    /// Registers a factory taking a runtime argument.  `resolve_memoized` runs it once per distinct
+    /// argument and hands out clones of that result afterwards.
+    #[cfg(feature = "std")]
+    pub fn register_memoized<T, A, F>(&self, factory: F) -> &Self where
+        T : Clone + 'static,
+        A : Hash + Eq + Clone + 'static,
+        F : Fn(&Container, &A) -> ContainerResult<T> + 'static
+    {
+        log_debug!("registering memoized factory for {}", self.display_name::<T>());
+        self.remember_type_name::<(T, A)>();
+        let memoized_factory = MemoizedFactory {
+            factory: Box::new(factory),
+            instances: RefCell::new(std::collections::HashMap::<A, T>::new())
+        };
+        self.memoized_factories.borrow_mut().insert(TypeId::of::<(T, A)>(), Rc::new(memoized_factory));
+        self
+    }
+
+    #[cfg(feature = "std")]
+    pub fn resolve_memoized<T, A>(&self, arg: A) -> ContainerResult<T> where
+        T : Clone + 'static,
+        A : Hash + Eq + Clone + 'static
+    {
+        self.check_phase()?;
+        let memoized_factory = match self.memoized_factories.borrow().get(&TypeId::of::<(T, A)>()) {
+            Some(memoized_factory) => memoized_factory.clone(),
+            None => return Err(self.missing_entry::<T>())
+        };
+        let memoized_factory = match memoized_factory.downcast::<MemoizedFactory<T, A>>() {
+            Ok(memoized_factory) => memoized_factory,
+            Err(_) => return Err(ContainerError::DowncastFailure)
+        };
+        let cached_instance = memoized_factory.instances.borrow().get(&arg).cloned();
+        match cached_instance {
+            Some(instance) => Ok(instance),
+            None => {
+                let new_instance = (memoized_factory.factory)(self, &arg)?;
+                memoized_factory.instances.borrow_mut().insert(arg, new_instance.clone());
+                Ok(new_instance)
+            }
+        }
+    }
+
+    pub fn collect_as<Dyn>(&self, coercions: &[Resolver<Arc<Dyn>>]) -> ContainerResult<Vec<Arc<Dyn>>> where
+        Dyn : ?Sized
+    {
+        coercions.iter().map(|coercion| coercion(self)).collect()
+    }
+
+    pub fn resolve_all_coerced<Dyn>(&self) -> ContainerResult<Vec<Arc<Dyn>>> where
+        Dyn : ?Sized + 'static
+    {
+        let coercions = self.coercions.borrow().get(&TypeId::of::<Arc<Dyn>>()).cloned().unwrap_or_default();
+        let mut instances = Vec::new();
+        for coercion in coercions.iter() {
+            match coercion.downcast_ref::<Resolver<Arc<Dyn>>>() {
+                Some(coercion) => instances.push(coercion(self)?),
+                None => return Err(ContainerError::DowncastFailure)
+            }
+        }
+        Ok(instances)
+    }
+
+    pub fn resolve_batch(&self, resolvers: &[Resolver<Box<dyn Any>>]) -> Vec<ContainerResult<Box<dyn Any>>> {
+        resolvers.iter().map(|resolver| resolver(self)).collect()
+    }
+
+    pub fn resolve_first<Out>(&self, candidates: &[Resolver<Out>]) -> ContainerResult<Out> {
+        let mut last_err = ContainerError::MissingEntry { type_name: type_name::<Out>().to_string() };
+        for candidate in candidates.iter() {
+            match candidate(self) {
+                Ok(resolved) => return Ok(resolved),
+                Err(err) if err.is_missing() => last_err = err,
+                Err(err) => return Err(err)
+            }
+        }
+        Err(last_err)
+    }
+
+    /// Resolves `T` with `context` registered as an instance of `C` for the duration of the call.
+    /// Anything cached while the context was visible is discarded afterwards, so singletons built
+    /// from one context never leak into later resolutions.
+    pub fn default_with_context<T, C>(&self, context: C) -> ContainerResult<T> where
+        T : Clone + 'static,
+        C : Clone + 'static
+    {
+        self.with_restored_registrations(|| {
+            self.register_instance(context);
+            self.default()
+        })
+    }
+
+    /// Runs `f` with every override in `overrides` registered, restoring the previous registrations
+    /// and discarding anything cached in the meantime once it returns, as `default_with_context` does.
+    pub fn resolve_with_overrides<T>(&self, overrides: OverrideSet, f: impl FnOnce(&Container) -> ContainerResult<T>) -> ContainerResult<T> {
+        self.with_restored_registrations(|| {
+            for apply_override in overrides.overrides {
+                apply_override(self);
+            }
+            f(self)
+        })
+    }
+
+    fn with_restored_registrations<R>(&self, f: impl FnOnce() -> R) -> R {
+        let entries = self.entries.borrow().clone();
+        let specialized_entries = self.specialized_entries.borrow().clone();
+        let named_entries = self.named_entries.borrow().clone();
+        let resolved_factories = self.resolved_factories.borrow().clone();
+        let cached_instances = self.cached_instances.borrow().clone();
+        let resolved_specialized_factories = self.resolved_specialized_factories.borrow().clone();
+        let resolved_named_factories = self.resolved_named_factories.borrow().clone();
+        let result = f();
+        *self.entries.borrow_mut() = entries;
+        *self.specialized_entries.borrow_mut() = specialized_entries;
+        *self.named_entries.borrow_mut() = named_entries;
+        *self.resolved_factories.borrow_mut() = resolved_factories;
+        *self.cached_instances.borrow_mut() = cached_instances;
+        *self.resolved_specialized_factories.borrow_mut() = resolved_specialized_factories;
+        *self.resolved_named_factories.borrow_mut() = resolved_named_factories;
+        result
+    }
+
+    pub fn resolve_into<T>(&self, slot: &mut T) -> ContainerResult<()> where
+        T : Clone + 'static
+    {
+        if !self.is_resolved::<T>() || self.pre_resolve_interceptor.borrow().is_some() {
+            *slot = self.default()?;
+            return Ok(());
+        }
+        self.check_phase()?;
+        self.record_dependency::<T>();
+        slot.clone_from(&*self.borrow_instance::<T>()?);
+        Ok(())
+    }
+
+    /// Borrows an already constructed instance without cloning it.  Only `Instance` entries and
+    /// factory results cached separately can be borrowed; a factory entry must be resolved through
+    /// `default` first.  The returned guard holds the table the instance lives in borrowed, so
+    /// registering or resolving a not yet constructed default while it is alive will panic.
+    pub fn borrow_instance<T>(&self) -> ContainerResult<InstanceRef<'_, T>> where
+        T : 'static
+    {
+        let type_id = TypeId::of::<T>();
+        if self.is_disabled(&type_id) {
+            return Err(self.missing_entry::<T>());
+        }
+        let entries = self.entries.borrow();
+        let is_factory = matches!(entries.get(&type_id), Some(ContainerEntry::Factory(_)));
+        let instance = match Ref::filter_map(entries, |entries| {
+            match entries.get(&type_id) {
+                Some(ContainerEntry::Instance(instance)) => instance.downcast_ref::<T>(),
+                _ => None
+            }
+        }) {
+            Ok(instance) => instance,
+            Err(_) if is_factory => {
+                match Ref::filter_map(self.cached_instances.borrow(), |cached_instances| {
+                    cached_instances.get(&type_id).and_then(|instance| instance.downcast_ref::<T>())
+                }) {
+                    Ok(instance) => instance,
+                    Err(_) => return Err(self.missing_entry::<T>())
+                }
+            },
+            Err(_) => return Err(self.missing_entry::<T>())
+        };
+        self.count_resolution(type_id);
+        Ok(InstanceRef { instance })
+    }
+
+    /// Resolves `T` and returns a plain reference that stays valid for as long as the container is
+    /// borrowed.  The resolved `Arc` is pinned in an append-only list that is only released when the
+    /// container drops, so re-registering or resetting `T` afterwards can't invalidate it.  Each
+    /// distinct instance is pinned once, but a transient `T` pins a new value on every call.
+    pub fn default_ref<T>(&self) -> ContainerResult<&T> where
+        T : 'static
+    {
+        let instance = self.resolve_default_entry(TypeId::of::<T>(), type_name::<T>())?;
+        let pointer: *const T = match instance.downcast_ref::<T>() {
+            Some(pinned) => pinned,
+            None => return Err(ContainerError::DowncastFailure)
+        };
+        let mut pinned_instances = self.pinned_instances.borrow_mut();
+        if !pinned_instances.iter().any(|pinned| Arc::ptr_eq(pinned, &instance)) {
+            pinned_instances.push(instance);
+        }
+        // SAFETY: the pointee lives in an `Arc` allocation that `pinned_instances` keeps alive.
+        // Entries are never removed from that list, and it is only dropped with the container,
+        // which can't happen while `self` is borrowed.
+        Ok(unsafe { &*pointer })
+    }
+
+    /// Like `default`, but borrows instead of cloning when `T` is already stored as an instance,
+    /// including a factory result cached by an earlier resolution.  `std::borrow::Cow` can't carry
+    /// the guard, hence `InstanceCow`; a `Borrowed` value has the same restrictions as
+    /// `borrow_instance` for as long as it is alive.
+    pub fn default_cow<T>(&self) -> ContainerResult<InstanceCow<'_, T>> where
+        T : Clone + 'static
+    {
+        self.check_phase()?;
+        if self.pre_resolve_interceptor.borrow().is_none() {
+            if let Ok(instance) = self.borrow_instance::<T>() {
+                self.record_dependency::<T>();
+                return Ok(InstanceCow::Borrowed(instance));
+            }
+        }
+        Ok(InstanceCow::Owned(self.default()?))
+    }
+
+    pub fn specialized<T, S>(&self, specialization: S) -> ContainerResult<T> where
+        T : Clone + 'static,
+        S : Copy + 'static,
+        i64 : From<S>,
+        S : TryFrom<i64>
+    {
+        self.check_phase()?;
+        self.record_dependency::<T>();
+
+        let specialized_entry_key = SpecializedEntryKey::new_for_specialization::<T, S>(specialization);
+
+        let specialized_entry = {
+            self.specialized_entries.borrow().get(&specialized_entry_key).cloned()
+        };
+
+        match specialized_entry {
             Some(container_entry) => {
+                self.count_resolution(specialized_entry_key.type_id);
                 match container_entry {
                     ContainerEntry::Instance(instance) => {
-                        Ok((*instance).downcast_ref::<T>().unwrap().clone())
+                        log_trace!("cache hit for {} specialized as {}", self.display_name::<T>(), specialized_entry_key.specialization_value);
+                        downcast_instance::<T>(&instance)
                     },
-                    ContainerEntry::Factory(factory) => {
-                        match factory(self) {
+                    ContainerEntry::SpecializedFactory(factory) => {
+                        self.specialization_stack.borrow_mut().push((TypeId::of::<S>(), specialized_entry_key.specialization_value));
+                        let result = self.invoke_factory::<T>(&factory);
+                        self.specialization_stack.borrow_mut().pop();
+                        match result {
                             Ok(new_instance) => {
+                                let resolved = downcast_instance::<T>(&new_instance)?;
                                 let new_entry = ContainerEntry::Instance(new_instance);
                                 {
-                                    let mut entries = self.entries.borrow_mut();
-                                    entries.insert(type_id, new_entry);
+                                    let mut specialized_entries = self.specialized_entries.borrow_mut();
+                                    specialized_entries.insert(specialized_entry_key, new_entry);
                                 }
-                                self.default()
+                                self.resolved_specialized_factories.borrow_mut().insert(specialized_entry_key, factory);
+                                Ok(resolved)
                             },
                             Err(err) => Err(err)
                         }
                     },
                     _ => {
-                        Err(ContainerError::MissingEntry)
+                        log_debug!("{} is registered as default, not specialized", self.display_name::<T>());
+                        Err(ContainerError::WrongEntryKind { expected: EntryKind::Specialized, found: EntryKind::Default })
                     }
                 }
             },
+            None if !self.has_specializations(TypeId::of::<T>()) && self.contains::<T>() => {
+                log_debug!("{} is registered as default, not specialized", self.display_name::<T>());
+                Err(ContainerError::WrongEntryKind { expected: EntryKind::Specialized, found: EntryKind::Default })
+            },
             None => {
-                Err(ContainerError::MissingEntry)
+                log_debug!("missing entry for {} specialized as {}", self.display_name::<T>(), specialized_entry_key.specialization_value);
+                Err(ContainerError::MissingSpecializedEntry { type_name: self.display_name::<T>() })
             }
         }
     }
 
-    pub fn specialized<T, S>(&self, specialization: S) -> ContainerResult<T> where
+    /// The innermost specialization of type `S` whose factory is currently running, if any.  Lets
+    /// a nested factory branch on the enclosing specialization; register it transient so a cached
+    /// value from one specialization isn't handed to another.
+    pub fn current_specialization<S>(&self) -> Option<S> where
+        S : Copy + 'static,
+        S : TryFrom<i64>
+    {
+        let specialization_value = self.specialization_stack.borrow()
+            .iter()
+            .rev()
+            .find(|(specialization_type_id, _)| *specialization_type_id == TypeId::of::<S>())
+            .map(|(_, specialization_value)| *specialization_value)?;
+        specialization_from_value(specialization_value).ok()
+    }
+
+    pub fn named<T>(&self, name: &str) -> ContainerResult<T> where
+        T : Clone + 'static
+    {
+        self.check_phase()?;
+        self.record_dependency::<T>();
+
+        let named_entry_key = (TypeId::of::<T>(), name.to_string());
+        let named_entry = {
+            self.named_entries.borrow().get(&named_entry_key).cloned()
+        };
+
+        match named_entry {
+            Some(ContainerEntry::Instance(instance)) => {
+                downcast_instance::<T>(&instance)
+            },
+            Some(ContainerEntry::Factory(factory)) => {
+                let new_instance = self.invoke_factory::<T>(&factory)?;
+                let resolved = downcast_instance::<T>(&new_instance)?;
+                self.named_entries.borrow_mut().insert(named_entry_key.clone(), ContainerEntry::Instance(new_instance));
+                self.resolved_named_factories.borrow_mut().insert(named_entry_key, factory);
+                Ok(resolved)
+            },
+            _ => Err(ContainerError::MissingNamedEntry { type_name: self.display_name::<T>() })
+        }
+    }
+
+    pub fn resolve_tagged<T>(&self, tag: &str) -> ContainerResult<Vec<T>> where
+        T : Clone + 'static
+    {
+        self.check_phase()?;
+        self.record_dependency::<T>();
+
+        let type_id = TypeId::of::<T>();
+        let tagged: Vec<(usize, TaggedEntry)> = match self.tagged_entries.borrow().get(tag) {
+            Some(tagged_entries) => tagged_entries.iter().cloned().enumerate()
+                .filter(|(_, tagged_entry)| tagged_entry.type_id == type_id)
+                .collect(),
+            None => Vec::new()
+        };
+
+        let mut instances = Vec::with_capacity(tagged.len());
+        for (index, tagged_entry) in tagged {
+            let instance = match tagged_entry.instance {
+                Some(instance) => instance,
+                None => {
+                    let new_instance = self.invoke_factory::<T>(&tagged_entry.factory)?;
+                    if let Some(tagged_entries) = self.tagged_entries.borrow_mut().get_mut(tag) {
+                        tagged_entries[index].instance = Some(new_instance.clone());
+                    }
+                    new_instance
+                }
+            };
+            instances.push(downcast_instance::<T>(&instance)?);
+        }
+        Ok(instances)
+    }
+
+    pub fn names_for<T>(&self) -> Vec<String> where
+        T : 'static
+    {
+        let type_id = TypeId::of::<T>();
+        let mut names: Vec<String> = self.named_entries.borrow().keys()
+            .filter(|(named_type_id, _)| *named_type_id == type_id)
+            .map(|(_, name)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    pub fn specialized_or_default<T, S>(&self, specialization: S) -> ContainerResult<T> where
         T : Clone + 'static,
         S : Copy + 'static,
-        i32 : From<S>,
-        S : From<i32>
+        i64 : From<S>,
+        S : TryFrom<i64>
     {
-        let specialized_entry_key = SpecializedEntryKey::new_for_specialization::<T, S>(specialization);
+        match self.specialized(specialization) {
+            Err(ContainerError::MissingSpecializedEntry { type_name: _ }) | Err(ContainerError::WrongEntryKind { expected: EntryKind::Specialized, found: EntryKind::Default }) => self.default(),
+            result => result
+        }
+    }
 
-        let specialized_entry = {
-            self.specialized_entries.borrow().get(&specialized_entry_key).cloned()
+    pub fn specialized_by<T, S>(&self, selector: impl Fn(&[S]) -> S) -> ContainerResult<T> where
+        T : Clone + 'static,
+        S : Copy + 'static,
+        i64 : From<S>,
+        S : TryFrom<i64>
+    {
+        let specializations: Vec<S> = self.known_specialization_values::<T, S>().into_iter()
+            .map(specialization_from_value)
+            .collect::<ContainerResult<_>>()?;
+        if specializations.is_empty() {
+            return Err(ContainerError::MissingSpecializedEntry { type_name: self.display_name::<T>() });
+        }
+        self.specialized(selector(&specializations))
+    }
+
+    pub fn specialized_i32<T>(&self, key: i32) -> ContainerResult<T> where
+        T : Clone + 'static
+    {
+        self.specialized::<T, i64>(i64::from(key))
+    }
+
+    pub fn specialized_range<T>(&self, range: Range<i32>, skip_missing: bool) -> ContainerResult<Vec<T>> where
+        T : Clone + 'static
+    {
+        let mut instances = Vec::new();
+        for key in range {
+            match self.specialized_i32(key) {
+                Ok(instance) => instances.push(instance),
+                Err(ContainerError::MissingSpecializedEntry { type_name: _ }) if skip_missing => continue,
+                Err(err) => return Err(err)
+            }
+        }
+        Ok(instances)
+    }
+
+    pub fn all_specialized<T, S>(&self) -> ContainerResult<Vec<T>> where
+        T : Clone + 'static,
+        S : Copy + 'static,
+        i64 : From<S>,
+        S : TryFrom<i64>
+    {
+        let mut instances = Vec::new();
+        for specialization_value in self.known_specialization_values::<T, S>() {
+            let specialization: S = specialization_from_value(specialization_value)?;
+            instances.push(self.specialized(specialization)?);
+        }
+        Ok(instances)
+    }
+
+    /// Like `all_specialized` for `Arc<T>`, but specializations that resolve to the same allocation,
+    /// e.g. several keys linked to one singleton, yield it only once.
+    pub fn all_specialized_unique<T, S>(&self) -> ContainerResult<Vec<Arc<T>>> where
+        T : ?Sized + 'static,
+        S : Copy + 'static,
+        i64 : From<S>,
+        S : TryFrom<i64>
+    {
+        let mut instances: Vec<Arc<T>> = Vec::new();
+        for specialization_value in self.known_specialization_values::<Arc<T>, S>() {
+            let specialization: S = specialization_from_value(specialization_value)?;
+            let instance: Arc<T> = self.specialized(specialization)?;
+            if !instances.iter().any(|seen| Arc::as_ptr(seen) as *const () == Arc::as_ptr(&instance) as *const ()) {
+                instances.push(instance);
+            }
+        }
+        Ok(instances)
+    }
+
+    pub fn try_all_specialized<T, S>(&self) -> Vec<(S, ContainerResult<T>)> where
+        T : Clone + 'static,
+        S : Copy + 'static,
+        i64 : From<S>,
+        S : TryFrom<i64>
+    {
+        self.known_specialization_values::<T, S>().into_iter()
+            .filter_map(|specialization_value| S::try_from(specialization_value).ok())
+            .map(|specialization| (specialization, self.specialized(specialization)))
+            .collect()
+    }
+
+    pub fn for_each_specialized<T, S>(&self, mut f: impl FnMut(&T)) -> ContainerResult<()> where
+        T : Clone + 'static,
+        S : Copy + 'static,
+        i64 : From<S>,
+        S : TryFrom<i64>
+    {
+        for specialization_value in self.known_specialization_values::<T, S>() {
+            let specialization: S = specialization_from_value(specialization_value)?;
+            let specialized_instance: T = self.specialized(specialization)?;
+            f(&specialized_instance);
+        }
+        Ok(())
+    }
+
+    /// The values in `expected` that have no specialized registration for `T`, in their given order.
+    pub fn missing_specializations<T, S>(&self, expected: &[S]) -> Vec<S> where
+        T : Clone + 'static,
+        S : Copy + 'static,
+        i64 : From<S>
+    {
+        let known_specialization_key = KnownSpecializationKey::new_for_specialization::<T, S>();
+        let specializations = self.specializations.borrow();
+        let specialization_values = specializations.get(&known_specialization_key);
+        expected.iter()
+            .copied()
+            .filter(|specialization| !specialization_values.is_some_and(|specialization_values| specialization_values.contains(&i64::from(*specialization))))
+            .collect()
+    }
+
+    /// Resolves one specialization per key, failing if any of them is missing.
+    pub fn specialized_array<T, S, const N: usize>(&self, keys: [S; N]) -> ContainerResult<[T; N]> where
+        T : Clone + 'static,
+        S : Copy + 'static,
+        i64 : From<S>,
+        S : TryFrom<i64>
+    {
+        let mut instances = Vec::with_capacity(N);
+        for specialization in keys.iter() {
+            instances.push(self.specialized(*specialization)?);
+        }
+        match <[T; N]>::try_from(instances) {
+            Ok(instances) => Ok(instances),
+            Err(_) => unreachable!("one instance is resolved per key")
+        }
+    }
+
+    pub fn specialized_map<T, S>(&self) -> ContainerResult<BTreeMap<i64, T>> where
+        T : Clone + 'static,
+        S : Copy + 'static,
+        i64 : From<S>,
+        S : TryFrom<i64>
+    {
+        let specialization_values = self.known_specialization_values::<T, S>();
+        let mut instances = BTreeMap::new();
+        for specialization_value in specialization_values {
+            let specialization: S = specialization_from_value(specialization_value)?;
+            instances.insert(specialization_value, self.specialized(specialization)?);
+        }
+        Ok(instances)
+    }
+
+    fn has_specializations(&self, type_id: TypeId) -> bool {
+        self.specializations.borrow().iter()
+            .any(|(known_specialization_key, specialization_values)| known_specialization_key.type_id == type_id && !specialization_values.is_empty())
+    }
+
+    fn known_specialization_values<T, S>(&self) -> Vec<i64> where
+        T : Clone + 'static,
+        S : Copy + 'static,
+        i64 : From<S>
+    {
+        let known_specialization_key = KnownSpecializationKey::new_for_specialization::<T, S>();
+        let mut specialization_values: Vec<i64> = match self.specializations.borrow().get(&known_specialization_key) {
+            Some(specialization_values) => specialization_values.iter().copied().collect(),
+            None => Vec::new()
         };
+        specialization_values.sort_unstable();
+        specialization_values
+    }
 
-        match specialized_entry {
+    pub fn run_factory<T>(&self, override_container: &Container, factory: impl Fn(&Container) -> ContainerResult<T>) -> ContainerResult<T> where
+        T : 'static
+    {
+        override_container.resolution_stack.borrow_mut().push(type_name::<T>());
+        let result = factory(override_container);
+        override_container.resolution_stack.borrow_mut().pop();
+        result
+    }
+
+    pub fn strict_phases(&self) -> &Self {
+        self.strict_phases.set(true);
+        self
+    }
+
+    pub fn begin_resolving(&self) -> &Self {
+        self.phase.set(Phase::Resolving);
+        self
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase.get()
+    }
+
+    /// Fails a resolution with `BudgetExceeded` once it has run more than `max_factory_runs`
+    /// factories.  The count covers the whole resolution, including factories a child's resolution
+    /// runs in this container and factories this container's resolution runs in its parents.
+    pub fn set_resolution_budget(&self, max_factory_runs: usize) -> &Self {
+        self.resolution_budget.set(Some(max_factory_runs));
+        self
+    }
+
+    /// Fails any factory that would run more than `limit` factories deep with `DepthExceeded`.  The
+    /// depth counts every nested factory of the resolution, whether it ran in this container, a
+    /// child that resolved through it or one of its parents.
+    pub fn set_max_depth(&self, limit: usize) -> &Self {
+        self.depth_limit.set(Some(limit));
+        self
+    }
+
+    /// Deepest factory nesting seen so far under this container's factories.  Depth is counted
+    /// across the whole resolution, so a parent factory run for a child sits below the child's
+    /// factory; a factory resolved from the top level has depth 1.
+    pub fn max_resolution_depth(&self) -> usize {
+        self.max_depth.get()
+    }
+
+    fn check_phase(&self) -> ContainerResult<()> {
+        if self.strict_phases.get() && self.phase.get() == Phase::Registering {
+            return Err(ContainerError::WrongPhase { phase: Phase::Registering });
+        }
+        Ok(())
+    }
+
+    pub fn record_dependencies(&self) -> &Self {
+        let mut dependency_graph = self.dependency_graph.borrow_mut();
+        if dependency_graph.is_none() {
+            *dependency_graph = Some(DependencyGraph::new());
+        }
+        self
+    }
+
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        self.dependency_graph.borrow().clone().unwrap_or_default()
+    }
+
+    fn record_dependency<T>(&self) where
+        T : 'static
+    {
+        self.record_dependency_on(type_name::<T>());
+    }
+
+    fn record_dependency_on(&self, type_name: &'static str) {
+        if let Some(dependency_graph) = self.dependency_graph.borrow_mut().as_mut() {
+            if let Some(parent) = self.resolution_stack.borrow().last() {
+                dependency_graph.add_edge(parent, type_name);
+            }
+        }
+    }
+
+    fn resolve_default_entry(&self, type_id: TypeId, type_name: &'static str) -> ContainerResult<Arc<dyn Any>> {
+        self.check_phase()?;
+        self.record_dependency_on(type_name);
+
+        let pre_resolve_interceptor = self.pre_resolve_interceptor.borrow().clone();
+        if let Some(instance) = pre_resolve_interceptor.and_then(|pre_resolve_interceptor| pre_resolve_interceptor(type_id)) {
+            log_trace!("pre-resolve interceptor supplied {}", self.display_name_for(&type_id, type_name));
+            return Ok(instance);
+        }
+
+        // Hashed once here and reused if a factory's instance is cached below.
+        let entry_key = self.entries.borrow().key(type_id);
+        let disabled = self.is_disabled(&type_id);
+        let entry = if disabled {
+            None
+        } else {
+            self.entries.borrow().get_by_key(&entry_key).cloned()
+        };
+
+        match entry {
             Some(container_entry) => {
+                self.count_resolution(type_id);
                 match container_entry {
                     ContainerEntry::Instance(instance) => {
-                        Ok((*instance).downcast_ref::<T>().unwrap().clone())
+                        log_trace!("cache hit for {}", self.display_name_for(&type_id, type_name));
+                        Ok(instance)
                     },
-                    ContainerEntry::SpecializedFactory(factory) => {
-                        match factory(self) {
+                    ContainerEntry::Factory(factory) if self.pure_resolution.get() || self.transient_entries.borrow().contains(&type_id) => {
+                        self.invoke_erased_factory(type_id, type_name, &factory)
+                    },
+                    ContainerEntry::Factory(factory) if self.separate_cache.get() => {
+                        let cached_instance = self.cached_instances.borrow().get(&type_id).cloned();
+                        match cached_instance {
+                            Some(instance) => {
+                                log_trace!("cache hit for {}", self.display_name_for(&type_id, type_name));
+                                Ok(instance)
+                            },
+                            None => {
+                                let new_instance = self.invoke_erased_factory(type_id, type_name, &factory)?;
+                                self.cached_instances.borrow_mut().insert(type_id, new_instance.clone());
+                                Ok(new_instance)
+                            }
+                        }
+                    },
+                    ContainerEntry::Factory(factory) => {
+                        match self.invoke_erased_factory(type_id, type_name, &factory) {
                             Ok(new_instance) => {
-                                let new_entry = ContainerEntry::Instance(new_instance);
-                                {
-                                    let mut specialized_entries = self.specialized_entries.borrow_mut();
-                            specialized_entries.insert(specialized_entry_key, new_entry);
-                                }
-                                self.specialized(specialization)
+                                self.entries.borrow_mut().insert_by_key(entry_key, ContainerEntry::Instance(new_instance.clone()));
+                                self.resolved_factories.borrow_mut().insert(type_id, factory);
+                                Ok(new_instance)
                             },
                             Err(err) => Err(err)
                         }
                     },
                     _ => {
-                        Err(ContainerError::MissingSpecializedEntry)
+                        log_debug!("{} is registered as specialized, not default", self.display_name_for(&type_id, type_name));
+                        Err(ContainerError::WrongEntryKind { expected: EntryKind::Default, found: EntryKind::Specialized })
                     }
                 }
             },
+            None if self.has_specializations(type_id) => {
+                log_debug!("{} is registered as specialized, not default", self.display_name_for(&type_id, type_name));
+                Err(ContainerError::WrongEntryKind { expected: EntryKind::Default, found: EntryKind::Specialized })
+            },
             None => {
-                Err(ContainerError::MissingSpecializedEntry)
+                // A disabled entry is still in the map, so neither a scoped factory nor the
+                // on-missing handler may register over it, but the parent and fallback still apply.
+                if !disabled {
+                    if let Some(factory) = self.scoped_factory(type_id) {
+                        self.entries.borrow_mut().insert(type_id, ContainerEntry::Factory(factory));
+                        return self.resolve_default_entry(type_id, type_name);
+                    }
+                }
+                if let Some(parent) = self.parent {
+                    match parent.resolve_default_entry(type_id, type_name) {
+                        Err(err) if err.is_missing() => { },
+                        result => return result
+                    }
+                }
+                let fallback_resolver = self.fallback_resolver.borrow().clone();
+                if let Some(instance) = fallback_resolver.and_then(|fallback_resolver| fallback_resolver(type_id)) {
+                    log_trace!("fallback resolver supplied {}", self.display_name_for(&type_id, type_name));
+                    return Ok(instance);
+                }
+                if !disabled {
+                    let on_missing = self.on_missing.borrow().clone();
+                    if let Some(on_missing) = on_missing {
+                        on_missing(self, type_id);
+                        if self.entries.borrow().contains_key(&type_id) {
+                            log_trace!("on-missing handler registered {}", self.display_name_for(&type_id, type_name));
+                            return self.resolve_default_entry(type_id, type_name);
+                        }
+                    }
+                }
+                let display_name = self.display_name_for(&type_id, type_name);
+                log_debug!("missing entry for {}", display_name);
+                let implementors = self.registered_implementors(type_id);
+                if implementors.is_empty() {
+                    Err(ContainerError::MissingEntry { type_name: display_name })
+                } else {
+                    Err(ContainerError::NotRegisteredAsTrait { type_name: display_name, implementors })
+                }
             }
         }
     }
 
-    pub fn all_specialized<T, S>(&self) -> ContainerResult<Vec<T>> where
-        T : Clone + 'static,
-        S : Copy + 'static,
-        i32 : From<S>,
-        S : From<i32>
+    fn record_trait_witness<Concrete, Dyn>(&self) where
+        Concrete : 'static,
+        Dyn : ?Sized + 'static
     {
-        let known_specialization_key = KnownSpecializationKey::new_for_specialization::<T, S>();
-        let mut specializations = self.specializations.borrow_mut();
-        let known_specializations_entry = specializations.entry(known_specialization_key)
-            .or_insert_with(|| { HashSet::new() });
-        let mut instances = Vec::new();
-        for specialization_value in known_specializations_entry.iter() {
-            let specialization: S = (*specialization_value).into();
-            match self.specialized(specialization) {
-                Ok(specialized_instance) => instances.push(specialized_instance),
-                Err(err) => return Err(err)
+        let trait_witness = TraitWitness {
+            concrete_type_id: TypeId::of::<Arc<Concrete>>(),
+            concrete_name: type_name::<Arc<Concrete>>()
+        };
+        let mut trait_witnesses = self.trait_witnesses.borrow_mut();
+        let witnesses = trait_witnesses.entry(TypeId::of::<Arc<Dyn>>()).or_default();
+        if !witnesses.iter().any(|witness| witness.concrete_type_id == trait_witness.concrete_type_id) {
+            witnesses.push(trait_witness);
+        }
+    }
+
+    fn registered_implementors(&self, type_id: TypeId) -> Vec<String> {
+        let trait_witnesses = self.trait_witnesses.borrow();
+        let entries = self.entries.borrow();
+        match trait_witnesses.get(&type_id) {
+            Some(witnesses) => witnesses.iter()
+                .filter(|witness| entries.contains_key(&witness.concrete_type_id))
+                .map(|witness| self.display_name_for(&witness.concrete_type_id, witness.concrete_name))
+                .collect(),
+            None => Vec::new()
+        }
+    }
+
+
+    // Factories re-enter the container to resolve their dependencies, so no borrow of an entry map
+    // may be held while one runs.  Callers clone the entry out of its map and drop the borrow first.
+    fn invoke_factory<T>(&self, factory: &AnyFactory) -> ContainerResult<Arc<dyn Any>> where
+        T : 'static
+    {
+        self.invoke_erased_factory(TypeId::of::<T>(), type_name::<T>(), factory)
+    }
+
+    fn invoke_erased_factory(&self, type_id: TypeId, type_name: &'static str, factory: &AnyFactory) -> ContainerResult<Arc<dyn Any>> {
+        debug_assert!(self.entries.try_borrow_mut().is_ok(), "entries borrowed across a factory call");
+        debug_assert!(self.specialized_entries.try_borrow_mut().is_ok(), "specialized entries borrowed across a factory call");
+        debug_assert!(self.named_entries.try_borrow_mut().is_ok(), "named entries borrowed across a factory call");
+        debug_assert!(self.tagged_entries.try_borrow_mut().is_ok(), "tagged entries borrowed across a factory call");
+        let factory_address = Arc::as_ptr(factory) as *const () as usize;
+        if self.active_factories.borrow().contains(&factory_address) {
+            log_debug!("circular dependency on {}", self.display_name_for(&type_id, type_name));
+            return Err(ContainerError::CircularDependency { type_name: self.display_name_for(&type_id, type_name) });
+        }
+        if let Some(dependency_graph) = self.dependency_graph.borrow_mut().as_mut() {
+            dependency_graph.add_node(type_name);
+        }
+        // A resolution can move from a child into its parents but never back down, so the state of
+        // one resolution across the chain is kept on the root.
+        let root = self.root();
+        let budget = self.chain().filter_map(|container| container.resolution_budget.get()).min();
+        let depth_limit = self.chain().filter_map(|container| container.depth_limit.get()).min();
+        if root.chain_depth.get() == 0 {
+            root.factory_runs.set(0);
+            root.chain_budget.set(budget);
+            root.chain_depth_limit.set(depth_limit);
+        }
+        root.factory_runs.set(root.factory_runs.get() + 1);
+        if let Some(max_factory_runs) = budget.into_iter().chain(root.chain_budget.get()).min() {
+            if root.factory_runs.get() > max_factory_runs {
+                log_debug!("resolution budget of {} exceeded by {}", max_factory_runs, self.display_name_for(&type_id, type_name));
+                return Err(ContainerError::BudgetExceeded);
             }
         }
-        Ok(instances)
+        let depth = root.chain_depth.get() + 1;
+        if let Some(depth_limit) = depth_limit.into_iter().chain(root.chain_depth_limit.get()).min() {
+            if depth > depth_limit {
+                log_debug!("resolution depth of {} exceeded by {}", depth_limit, self.display_name_for(&type_id, type_name));
+                return Err(ContainerError::DepthExceeded);
+            }
+        }
+        log_debug!("running factory for {}", self.display_name_for(&type_id, type_name));
+        self.total_factory_runs.set(self.total_factory_runs.get() + 1);
+        root.chain_depth.set(depth);
+        let enclosing_deepest = root.deepest_chain_depth.replace(depth);
+        self.resolution_stack.borrow_mut().push(type_name);
+        self.active_factories.borrow_mut().push(factory_address);
+        let result = factory(self);
+        self.active_factories.borrow_mut().pop();
+        self.resolution_stack.borrow_mut().pop();
+        root.chain_depth.set(depth - 1);
+        // The deepest nesting reached under this factory, wherever in the chain it ran.
+        let deepest = root.deepest_chain_depth.get();
+        self.max_depth.set(self.max_depth.get().max(deepest));
+        root.deepest_chain_depth.set(enclosing_deepest.max(deepest));
+        if let Err(_err) = &result {
+            log_debug!("factory for {} failed: {}", self.display_name_for(&type_id, type_name), _err);
+        }
+        result
+    }
+
+    // Moves everything `staged` was given during `register_profile` into this container, so a
+    // profile can use any registration method, not just the entry maps.
+    fn apply_staged(&self, staged: &Container) {
+        for (type_id, mut shadowed_entries) in mem::take(&mut *staged.shadowed_entries.borrow_mut()) {
+            // An override staged without an earlier staged registration shadows whatever this
+            // container has now, which is what popping it should restore.
+            for shadowed_entry in shadowed_entries.iter_mut().filter(|shadowed_entry| shadowed_entry.entry.is_none()) {
+                shadowed_entry.entry = self.entries.borrow().get(&type_id).cloned();
+                shadowed_entry.resolved_factory = self.resolved_factories.borrow().get(&type_id).cloned();
+            }
+            self.shadowed_entries.borrow_mut().entry(type_id).or_default().extend(shadowed_entries);
+        }
+        for (type_id, container_entry) in mem::take(&mut *staged.entries.borrow_mut()).into_entries() {
+            self.entries.borrow_mut().insert(type_id, container_entry);
+            self.resolved_factories.borrow_mut().remove(&type_id);
+            self.cached_instances.borrow_mut().remove(&type_id);
+        }
+        for (specialized_entry_key, container_entry) in mem::take(&mut *staged.specialized_entries.borrow_mut()) {
+            self.resolved_specialized_factories.borrow_mut().remove(&specialized_entry_key);
+            self.specialized_entries.borrow_mut().insert(specialized_entry_key, container_entry);
+        }
+        for (named_entry_key, container_entry) in mem::take(&mut *staged.named_entries.borrow_mut()) {
+            self.resolved_named_factories.borrow_mut().remove(&named_entry_key);
+            self.named_entries.borrow_mut().insert(named_entry_key, container_entry);
+        }
+        for (tag, tagged_entries) in mem::take(&mut *staged.tagged_entries.borrow_mut()) {
+            self.tagged_entries.borrow_mut().entry(tag).or_default().extend(tagged_entries);
+        }
+        for (type_id, coercions) in mem::take(&mut *staged.coercions.borrow_mut()) {
+            self.coercions.borrow_mut().entry(type_id).or_default().extend(coercions);
+        }
+        for (type_id, witnesses) in mem::take(&mut *staged.trait_witnesses.borrow_mut()) {
+            self.trait_witnesses.borrow_mut().entry(type_id).or_default().extend(witnesses);
+        }
+        for (known_specialization_key, specialization_values) in mem::take(&mut *staged.specializations.borrow_mut()) {
+            self.specializations.borrow_mut().entry(known_specialization_key).or_default().extend(specialization_values);
+        }
+        self.specialization_provenance.borrow_mut().extend(mem::take(&mut *staged.specialization_provenance.borrow_mut()));
+        self.type_names.borrow_mut().extend(mem::take(&mut *staged.type_names.borrow_mut()));
+        self.type_aliases.borrow_mut().extend(mem::take(&mut *staged.type_aliases.borrow_mut()));
+        self.requirements.borrow_mut().extend(mem::take(&mut *staged.requirements.borrow_mut()));
+        self.scoped_factories.borrow_mut().extend(mem::take(&mut *staged.scoped_factories.borrow_mut()));
+        self.rc_entries.borrow_mut().extend(mem::take(&mut *staged.rc_entries.borrow_mut()));
+        self.sealed_entries.borrow_mut().extend(mem::take(&mut *staged.sealed_entries.borrow_mut()));
+        self.transient_entries.borrow_mut().extend(mem::take(&mut *staged.transient_entries.borrow_mut()));
+        self.disabled_entries.borrow_mut().extend(mem::take(&mut *staged.disabled_entries.borrow_mut()));
+        #[cfg(feature = "std")]
+        self.memoized_factories.borrow_mut().extend(mem::take(&mut *staged.memoized_factories.borrow_mut()));
+        self.pinned_instances.borrow_mut().extend(mem::take(&mut *staged.pinned_instances.borrow_mut()));
+        self.disposers.borrow_mut().extend(mem::take(&mut *staged.disposers.borrow_mut()));
+        for (profile, staged_containers) in mem::take(&mut *staged.profiles.borrow_mut()) {
+            self.profiles.borrow_mut().entry(profile).or_default().extend(staged_containers);
+        }
+        if let Some(fallback_resolver) = staged.fallback_resolver.borrow_mut().take() {
+            *self.fallback_resolver.borrow_mut() = Some(fallback_resolver);
+        }
+        if let Some(pre_resolve_interceptor) = staged.pre_resolve_interceptor.borrow_mut().take() {
+            *self.pre_resolve_interceptor.borrow_mut() = Some(pre_resolve_interceptor);
+        }
+        if let Some(on_missing) = staged.on_missing.borrow_mut().take() {
+            *self.on_missing.borrow_mut() = Some(on_missing);
+        }
+        if staged.separate_cache.get() {
+            self.separate_cache.set(true);
+        }
+        if staged.pure_resolution.get() {
+            self.pure_resolution.set(true);
+        }
+        if staged.strict_phases.get() {
+            self.strict_phases.set(true);
+        }
+        if staged.phase.get() == Phase::Resolving {
+            self.phase.set(Phase::Resolving);
+        }
+        if let Some(max_factory_runs) = staged.resolution_budget.get() {
+            self.resolution_budget.set(Some(max_factory_runs));
+        }
+        if let Some(limit) = staged.depth_limit.get() {
+            self.depth_limit.set(Some(limit));
+        }
+        if staged.dependency_graph.borrow().is_some() {
+            self.record_dependencies();
+        }
+    }
+
+    fn cache_default_instance<T>(&self, instance: Arc<dyn Any>) -> ContainerResult<()> where
+        T : 'static
+    {
+        let type_id = TypeId::of::<T>();
+        let entry = self.entries.borrow().get(&type_id).cloned();
+        match entry {
+            Some(ContainerEntry::Factory(factory)) => {
+                self.resolved_factories.borrow_mut().insert(type_id, factory);
+            },
+            Some(_) => { },
+            None => return Err(self.missing_entry::<T>())
+        }
+        self.entries.borrow_mut().insert(type_id, ContainerEntry::Instance(instance));
+        Ok(())
+    }
+
+    fn lifetime_of_type(&self, type_id: TypeId) -> Option<Lifetime> {
+        if self.scoped_factory(type_id).is_some() {
+            return Some(Lifetime::Scoped);
+        }
+        match self.entries.borrow().get(&type_id) {
+            Some(ContainerEntry::Factory(_)) if self.pure_resolution.get() || self.transient_entries.borrow().contains(&type_id) => Some(Lifetime::Transient),
+            Some(_) => Some(Lifetime::Singleton),
+            None if self.has_specializations(type_id) => Some(Lifetime::Singleton),
+            None => match self.parent {
+                Some(parent) => parent.lifetime_of_type(type_id),
+                None => None
+            }
+        }
+    }
+
+    fn scoped_factory(&self, type_id: TypeId) -> Option<AnyFactory> {
+        let mut container = Some(self);
+        while let Some(scope) = container {
+            if let Some(factory) = scope.scoped_factories.borrow().get(&type_id) {
+                return Some(factory.clone());
+            }
+            container = scope.parent;
+        }
+        None
+    }
+
+    fn count_resolution(&self, type_id: TypeId) {
+        *self.resolution_counts.borrow_mut().entry(type_id).or_insert(0) += 1;
+    }
+
+    fn remember_type_name<T>(&self) where
+        T : 'static
+    {
+        self.type_names.borrow_mut().insert(TypeId::of::<T>(), type_name::<T>());
+    }
+
+    fn type_name_of(&self, type_id: &TypeId) -> String {
+        match self.type_aliases.borrow().get(type_id) {
+            Some(display_name) => display_name.clone(),
+            None => self.type_names.borrow().get(type_id).copied().unwrap_or("<unknown>").to_string()
+        }
     }
 
     fn register_specialization<T, S>(&self, specialization: S) where
         T : Clone + 'static,
         S : Copy + 'static,
-        i32 : From<S>,
-        S : From<i32>
+        i64 : From<S>,
+        S : TryFrom<i64>
     {
+        self.remember_type_name::<T>();
+        self.remember_type_name::<S>();
         let known_specialization_key = KnownSpecializationKey::new_for_specialization::<T, S>();
         let mut specializations = self.specializations.borrow_mut();
         let known_specializations_entry = specializations.entry(known_specialization_key)
-            .or_insert_with(|| { HashSet::new() });
-        let specialization_value: i32 = specialization.into();
+            .or_insert_with(|| { Set::new() });
+        let specialization_value: i64 = specialization.into();
         known_specializations_entry.insert(specialization_value);
+        let specialized_entry_key = SpecializedEntryKey::new_for_specialization::<T, S>(specialization);
+        self.specialization_provenance.borrow_mut().insert(specialized_entry_key, Box::new(specialization));
     }
 }
@@ -0,0 +1,21 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::EntryKind;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContainerMetadata {
+    pub entries: Vec<EntryMetadata>
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EntryMetadata {
+    pub type_name: String,
+    pub kind: EntryKind,
+    pub specialization_type_name: Option<String>,
+    pub specialization_value: Option<i64>,
+    pub name: Option<String>,
+    pub resolved: bool
+}
@@ -0,0 +1,103 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::{Container, ContainerResult, Lifetime};
+
+type BoxedFactory<T> = Box<dyn Fn(&Container) -> ContainerResult<T>>;
+
+type Decorator<T> = Box<dyn Fn(T) -> T>;
+
+type InitHook<T> = Box<dyn Fn(&T)>;
+
+/// Accumulates a default registration for `T`, returned by `Container::register`.  Nothing is
+/// registered until `build` is called or the builder is dropped, and only if a factory was given.
+pub struct RegistrationBuilder<'a, 'container, T> where
+    T : Clone + 'static
+{
+    container: &'a Container<'container>,
+    lifetime: Lifetime,
+    factory: Option<BoxedFactory<T>>,
+    decorators: Vec<Decorator<T>>,
+    init_hooks: Vec<InitHook<T>>
+}
+
+impl<'a, 'container, T> RegistrationBuilder<'a, 'container, T> where
+    T : Clone + 'static
+{
+    pub(crate) fn new(container: &'a Container<'container>) -> Self {
+        Self {
+            container,
+            lifetime: Lifetime::Singleton,
+            factory: None,
+            decorators: Vec::new(),
+            init_hooks: Vec::new()
+        }
+    }
+
+    pub fn as_singleton(mut self) -> Self {
+        self.lifetime = Lifetime::Singleton;
+        self
+    }
+
+    pub fn as_transient(mut self) -> Self {
+        self.lifetime = Lifetime::Transient;
+        self
+    }
+
+    pub fn as_scoped(mut self) -> Self {
+        self.lifetime = Lifetime::Scoped;
+        self
+    }
+
+    pub fn from_factory(mut self, factory: impl Fn(&Container) -> ContainerResult<T> + 'static) -> Self {
+        self.factory = Some(Box::new(factory));
+        self
+    }
+
+    /// Decorators wrap the factory's result in the order they were added.
+    pub fn with_decorator(mut self, decorator: impl Fn(T) -> T + 'static) -> Self {
+        self.decorators.push(Box::new(decorator));
+        self
+    }
+
+    /// Init hooks see the fully decorated value each time the factory runs.
+    pub fn on_init(mut self, init_hook: impl Fn(&T) + 'static) -> Self {
+        self.init_hooks.push(Box::new(init_hook));
+        self
+    }
+
+    pub fn build(mut self) -> &'a Container<'container> {
+        self.commit();
+        self.container
+    }
+
+    fn commit(&mut self) {
+        let factory = match self.factory.take() {
+            Some(factory) => factory,
+            None => return
+        };
+        let decorators = mem::take(&mut self.decorators);
+        let init_hooks = mem::take(&mut self.init_hooks);
+        self.container
+            .register_factory_configurable(move |container: &Container| -> ContainerResult<T> {
+                let mut instance = factory(container)?;
+                for decorator in decorators.iter() {
+                    instance = decorator(instance);
+                }
+                for init_hook in init_hooks.iter() {
+                    init_hook(&instance);
+                }
+                Ok(instance)
+            })
+            .set_lifetime(self.lifetime);
+    }
+}
+
+impl<'a, 'container, T> Drop for RegistrationBuilder<'a, 'container, T> where
+    T : Clone + 'static
+{
+    fn drop(&mut self) {
+        self.commit();
+    }
+}
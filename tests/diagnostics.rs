@@ -0,0 +1,117 @@
+#![allow(clippy::arc_with_non_send_sync)]
+
+use rust_container::{Container, ContainerError, ContainerResult};
+use std::any::{type_name, Any, TypeId};
+use std::sync::Arc;
+
+#[derive(Clone, Debug)]
+struct Inventory;
+
+#[derive(Clone, Debug)]
+struct Checkout;
+
+#[derive(Clone, Debug)]
+struct Ledger;
+
+#[derive(Clone, Debug)]
+struct Cycle;
+
+#[test]
+fn aliased_types_render_their_alias() {
+    let container = Container::new();
+    container
+        .register_factory_named_type("inventory", |_container: &Container| -> ContainerResult<Inventory> { Ok(Inventory) })
+        .require::<Inventory>();
+    assert_eq!(container.display_name::<Inventory>(), "inventory");
+    assert_eq!(container.display_name::<Checkout>(), type_name::<Checkout>());
+    assert!(format!("{:?}", container).contains("inventory (default, factory)"));
+
+    container.disable::<Inventory>();
+    let missing = container.default::<Inventory>().unwrap_err();
+    assert!(matches!(&missing, ContainerError::MissingEntry { type_name } if type_name == "inventory"));
+    assert_eq!(missing.to_string(), "MissingEntry(inventory)");
+    assert_eq!(container.specialized::<Inventory, i32>(1).unwrap_err().to_string(), "MissingSpecializedEntry(inventory)");
+    assert_eq!(container.named::<Inventory>("main").unwrap_err().to_string(), "MissingNamedEntry(inventory)");
+    match container.validate() {
+        Err(ContainerError::UnsatisfiedRequirements { type_names }) => assert_eq!(type_names, ["inventory"]),
+        _ => panic!("expected UnsatisfiedRequirements")
+    }
+}
+
+#[test]
+fn debug_output_lists_registrations_with_their_kind() {
+    let container = Container::new();
+    container
+        .register_instance(Inventory)
+        .register_factory(|_container: &Container| -> ContainerResult<Checkout> { Ok(Checkout) })
+        .register_factory(|_container: &Container| -> ContainerResult<Ledger> { Ok(Ledger) })
+        .register_specialized_factory(2i32, |_container: &Container| -> ContainerResult<u32> { Ok(2) })
+        .register_named_instance("audit", Ledger);
+    container.default::<Ledger>().unwrap();
+
+    let debug = format!("{:?}", container);
+    assert!(debug.starts_with("Container {"));
+    assert!(debug.contains(&format!("{} (default, instance)", type_name::<Inventory>())));
+    assert!(debug.contains(&format!("{} (default, factory)", type_name::<Checkout>())));
+    assert!(debug.contains(&format!("{} (default, cached)", type_name::<Ledger>())));
+    assert!(debug.contains("u32 (specialized 2, factory)"));
+    // Registrations are listed as debug-formatted strings, so the name's quotes come out escaped.
+    assert!(debug.contains(&format!(r#"{} (named \"audit\", instance)"#, type_name::<Ledger>())));
+}
+
+#[test]
+fn error_predicates_classify_representative_errors() {
+    let container = Container::new();
+    container
+        .register_factory(|container: &Container| -> ContainerResult<Cycle> { container.default::<Cycle>() })
+        .register_factory(|_container: &Container| -> ContainerResult<Ledger> {
+            Err(ContainerError::FactoryError { error: Box::new(std::fmt::Error) })
+        })
+        .set_fallback_resolver(|type_id: TypeId| -> Option<Arc<dyn Any>> {
+            if type_id == TypeId::of::<Checkout>() { Some(Arc::new(Inventory)) } else { None }
+        });
+
+    let missing = container.default::<Inventory>().unwrap_err();
+    assert!(missing.is_missing() && !missing.is_factory_error());
+    let missing_specialized = container.specialized::<Inventory, i32>(1).unwrap_err();
+    assert!(missing_specialized.is_missing());
+    let missing_named = container.named::<Inventory>("main").unwrap_err();
+    assert!(missing_named.is_missing());
+
+    let factory_error = container.default::<Ledger>().unwrap_err();
+    assert!(factory_error.is_factory_error() && !factory_error.is_missing());
+    let cycle = container.default::<Cycle>().unwrap_err();
+    assert!(matches!(&cycle, ContainerError::CircularDependency { type_name: cyclic } if cyclic == type_name::<Cycle>()));
+    assert!(!cycle.is_missing() && !cycle.is_factory_error());
+    let downcast = container.default::<Checkout>().unwrap_err();
+    assert!(matches!(downcast, ContainerError::DowncastFailure));
+    assert!(!downcast.is_missing() && !downcast.is_factory_error());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn factory_errors_expose_their_source() {
+    use std::error::Error;
+
+    let factory_error = ContainerError::FactoryError { error: Box::new(std::fmt::Error) };
+    assert_eq!(factory_error.to_string(), "FactoryError");
+    assert_eq!(factory_error.source().unwrap().to_string(), std::fmt::Error.to_string());
+    assert!(ContainerError::MissingEntry { type_name: String::from("inventory") }.source().is_none());
+}
+
+#[test]
+fn unused_registrations_lists_what_was_never_resolved() {
+    let container = Container::new();
+    container
+        .register_instance(Inventory)
+        .register_factory(|_container: &Container| -> ContainerResult<Checkout> { Ok(Checkout) })
+        .register_factory(|_container: &Container| -> ContainerResult<Ledger> { Ok(Ledger) });
+    container.default::<Checkout>().unwrap();
+    container.default::<Checkout>().unwrap();
+
+    assert_eq!(container.resolution_count::<Checkout>(), 2);
+    assert_eq!(container.resolution_count::<Ledger>(), 0);
+    let mut expected = [type_name::<Inventory>(), type_name::<Ledger>()];
+    expected.sort_unstable();
+    assert_eq!(container.unused_registrations(), expected);
+}
@@ -0,0 +1,111 @@
+#![allow(clippy::arc_with_non_send_sync)]
+
+use rust_container::{Container, ContainerResult, Dispose, EntryState, Lifetime};
+use std::any::type_name;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+struct Engine;
+
+struct Car;
+
+#[derive(Clone)]
+struct Garage;
+
+trait Vehicle { }
+
+impl Vehicle for Car { }
+
+#[derive(Clone)]
+struct Session;
+
+impl Dispose for Session {
+    fn dispose(&self) { }
+}
+
+#[test]
+fn dependency_graph_records_edges_between_factories() {
+    let container = Container::new();
+    container
+        .record_dependencies()
+        .register_factory(|_container: &Container| -> ContainerResult<Arc<Engine>> { Ok(Arc::new(Engine)) })
+        .register_factory(|container: &Container| -> ContainerResult<Arc<Car>> {
+            container.default::<Arc<Engine>>()?;
+            Ok(Arc::new(Car))
+        })
+        .register_factory(|container: &Container| -> ContainerResult<Garage> {
+            container.default::<Arc<Car>>()?;
+            Ok(Garage)
+        });
+    container.default::<Garage>().unwrap();
+
+    let graph = container.dependency_graph();
+    let edges: &BTreeMap<&'static str, Vec<&'static str>> = graph.edges();
+    assert_eq!(edges[type_name::<Garage>()], [type_name::<Arc<Car>>()]);
+    assert_eq!(graph.dependencies_of(type_name::<Arc<Car>>()), [type_name::<Arc<Engine>>()]);
+    assert!(graph.dependencies_of(type_name::<Arc<Engine>>()).is_empty());
+    assert!(graph.to_dot().contains(&format!("\"{}\" -> \"{}\";", type_name::<Garage>(), type_name::<Arc<Car>>())));
+}
+
+#[test]
+fn instance_strong_count_reflects_clones_held_by_consumers() {
+    for separate_cache in [false, true].iter() {
+        let container = Container::new();
+        if *separate_cache {
+            container.cache_separately();
+        }
+        container.register_factory(|_container: &Container| -> ContainerResult<Arc<Engine>> { Ok(Arc::new(Engine)) });
+        assert_eq!(container.instance_strong_count::<Engine>(), None);
+
+        let held: Vec<Arc<Engine>> = (0..3).map(|_| container.default::<Arc<Engine>>().unwrap()).collect();
+        assert_eq!(container.instance_strong_count::<Engine>(), Some(4));
+
+        drop(held);
+        assert_eq!(container.instance_strong_count::<Engine>(), Some(1));
+
+        container.disable::<Arc<Engine>>();
+        assert_eq!(container.instance_strong_count::<Engine>(), None);
+    }
+}
+
+#[test]
+fn entry_kinds_classifies_every_kind_of_registration() {
+    let container = Container::new();
+    container
+        .register_instance(1u8)
+        .register_factory(|_container: &Container| -> ContainerResult<u16> { Ok(2) })
+        .register_factory(|_container: &Container| -> ContainerResult<u32> { Ok(3) })
+        .register_factory(|_container: &Container| -> ContainerResult<i16> { Ok(5) })
+        .register_specialized_factory(1i32, |_container: &Container| -> ContainerResult<i32> { Ok(6) })
+        .register_scoped(|_container: &Container| -> ContainerResult<Session> { Ok(Session) })
+        .register_rc(Rc::new(7i64))
+        .register_factory(|_container: &Container| -> ContainerResult<Arc<Car>> { Ok(Arc::new(Car)) })
+        .register_coercion(|car: Arc<Car>| -> Arc<dyn Vehicle> { car });
+    container.register_factory_configurable(|_container: &Container| -> ContainerResult<u64> { Ok(8) }).set_lifetime(Lifetime::Transient);
+    container.default::<u32>().unwrap();
+    container.disable::<i16>();
+
+    let entry_kinds = container.entry_kinds();
+    let state_of = |name: &str| -> Vec<EntryState> {
+        entry_kinds.iter().filter(|(type_name, _)| type_name == name).map(|(_, entry_state)| *entry_state).collect()
+    };
+    assert_eq!(state_of("u8"), [EntryState::Instance]);
+    assert_eq!(state_of("u16"), [EntryState::Factory]);
+    assert_eq!(state_of("u32"), [EntryState::Cached]);
+    assert_eq!(state_of("u64"), [EntryState::Transient]);
+    assert_eq!(state_of("i16"), [EntryState::Disabled]);
+    assert_eq!(state_of("i32"), [EntryState::SpecializedFactory]);
+    assert_eq!(state_of("i64"), [EntryState::Rc]);
+    assert_eq!(state_of(type_name::<Session>()), [EntryState::Scoped]);
+    assert_eq!(state_of(type_name::<Arc<dyn Vehicle>>()), [EntryState::Coercion]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn entry_kinds_lists_memoized_factories() {
+    let container = Container::new();
+    container.register_memoized(|_container: &Container, id: &u32| -> ContainerResult<String> { Ok(id.to_string()) });
+
+    assert_eq!(container.entry_kinds(), [(String::from(type_name::<(String, u32)>()), EntryState::Memoized)]);
+}
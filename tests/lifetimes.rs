@@ -0,0 +1,353 @@
+#![allow(clippy::arc_with_non_send_sync)]
+
+use rust_container::{Container, ContainerError, ContainerResult, Dispose, InstanceCow, Lifetime};
+use std::any::type_name;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::Arc;
+
+#[derive(Clone, Debug, PartialEq)]
+struct Counter {
+    value: usize
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Label {
+    text: String
+}
+
+#[derive(Clone)]
+struct Session {
+    id: u32,
+    disposed: Rc<RefCell<Vec<u32>>>
+}
+
+impl Dispose for Session {
+    fn dispose(&self) {
+        self.disposed.borrow_mut().push(self.id);
+    }
+}
+
+// Registers a `Counter` factory whose value is the number of times it has run.
+fn register_counter(container: &Container) -> Rc<Cell<usize>> {
+    let runs = Rc::new(Cell::new(0));
+    let factory_runs = runs.clone();
+    container.register_factory(move |_container: &Container| -> ContainerResult<Counter> {
+        factory_runs.set(factory_runs.get() + 1);
+        Ok(Counter { value: factory_runs.get() })
+    });
+    runs
+}
+
+#[test]
+fn overrides_follow_stack_discipline() {
+    let container = Container::new();
+    container.register_instance(Label { text: String::from("base") });
+    container.push_override(|_container: &Container| -> ContainerResult<Label> { Ok(Label { text: String::from("first") }) });
+    container.push_override(|_container: &Container| -> ContainerResult<Label> { Ok(Label { text: String::from("second") }) });
+
+    assert_eq!(container.default::<Label>().unwrap().text, "second");
+    assert!(container.pop_override::<Label>());
+    assert_eq!(container.default::<Label>().unwrap().text, "first");
+    assert!(container.pop_override::<Label>());
+    assert_eq!(container.default::<Label>().unwrap().text, "base");
+    assert!(!container.pop_override::<Label>());
+}
+
+#[test]
+fn reset_cache_rebuilds_every_singleton() {
+    let container = Container::new();
+    let runs = register_counter(&container);
+    container.register_factory(|container: &Container| -> ContainerResult<Label> {
+        Ok(Label { text: container.default::<Counter>()?.value.to_string() })
+    });
+
+    assert_eq!(container.default::<Label>().unwrap().text, "1");
+    assert_eq!(container.cached_count(), 2);
+    container.reset_cache();
+    assert_eq!(container.cached_count(), 0);
+    assert_eq!(container.default::<Label>().unwrap().text, "2");
+    assert_eq!(runs.get(), 2);
+}
+
+#[test]
+fn init_hook_runs_once_per_constructed_instance() {
+    let inits = Rc::new(Cell::new(0));
+    let singleton_inits = inits.clone();
+    let container = Container::new();
+    container.register_factory_with_init(
+        |_container: &Container| -> ContainerResult<Counter> { Ok(Counter { value: 1 }) },
+        move |_container: &Container, _counter: &Counter| singleton_inits.set(singleton_inits.get() + 1)
+    );
+    container.default::<Counter>().unwrap();
+    container.default::<Counter>().unwrap();
+    assert_eq!(inits.get(), 1);
+
+    let transient_inits = inits.clone();
+    container
+        .register::<Label>()
+        .as_transient()
+        .from_factory(|_container: &Container| -> ContainerResult<Label> { Ok(Label { text: String::from("transient") }) })
+        .on_init(move |_label: &Label| transient_inits.set(transient_inits.get() + 1))
+        .build();
+    container.default::<Label>().unwrap();
+    container.default::<Label>().unwrap();
+    assert_eq!(inits.get(), 3);
+}
+
+#[test]
+fn separately_cached_singletons_leave_the_factory_entry_alone() {
+    let container = Container::new();
+    container.cache_separately();
+    let runs = register_counter(&container);
+    let factory = container.factory_for::<Counter>().unwrap();
+
+    assert_eq!(container.default::<Counter>().unwrap().value, 1);
+    assert_eq!(container.default::<Counter>().unwrap().value, 1);
+    assert_eq!(runs.get(), 1);
+    assert!(container.is_resolved::<Counter>());
+    assert!(Arc::ptr_eq(&factory, &container.factory_for::<Counter>().unwrap()));
+}
+
+#[test]
+fn separately_cached_singletons_resolve_into_a_slot_repeatedly() {
+    let container = Container::new();
+    container.cache_separately();
+    let runs = register_counter(&container);
+
+    let mut slot = Counter { value: 0 };
+    container.resolve_into(&mut slot).unwrap();
+    assert_eq!(slot.value, 1);
+    slot.value = 0;
+    container.resolve_into(&mut slot).unwrap();
+    assert_eq!(slot.value, 1);
+    assert_eq!(runs.get(), 1);
+}
+
+#[test]
+fn separately_cached_singletons_are_borrowed_by_default_cow() {
+    let container = Container::new();
+    container.cache_separately();
+    register_counter(&container);
+
+    assert!(matches!(container.default_cow::<Counter>().unwrap(), InstanceCow::Owned(_)));
+    match container.default_cow::<Counter>().unwrap() {
+        InstanceCow::Borrowed(counter) => assert_eq!(counter.value, 1),
+        InstanceCow::Owned(_) => panic!("expected the cached instance to be borrowed")
+    };
+}
+
+#[test]
+fn configurable_lifetime_switches_between_singleton_and_transient() {
+    let container = Container::new();
+    let runs = Rc::new(Cell::new(0));
+    let factory_runs = runs.clone();
+    let handle = container.register_factory_configurable(move |_container: &Container| -> ContainerResult<Counter> {
+        factory_runs.set(factory_runs.get() + 1);
+        Ok(Counter { value: factory_runs.get() })
+    });
+    assert_eq!(handle.lifetime(), Lifetime::Singleton);
+    assert_eq!(container.default::<Counter>().unwrap().value, 1);
+    assert_eq!(container.default::<Counter>().unwrap().value, 1);
+
+    handle.set_lifetime(Lifetime::Transient);
+    assert_eq!(handle.lifetime(), Lifetime::Transient);
+    assert_eq!(container.default::<Counter>().unwrap().value, 2);
+    assert_eq!(container.default::<Counter>().unwrap().value, 3);
+
+    handle.set_lifetime(Lifetime::Singleton);
+    assert_eq!(container.default::<Counter>().unwrap().value, 4);
+    assert_eq!(container.default::<Counter>().unwrap().value, 4);
+}
+
+#[test]
+fn scoped_singleton_is_disposed_with_the_child_that_built_it() {
+    let disposed = Rc::new(RefCell::new(Vec::new()));
+    let next_id = Rc::new(Cell::new(0));
+    let parent = Container::new();
+    let sessions = disposed.clone();
+    parent.register_scoped(move |_container: &Container| -> ContainerResult<Session> {
+        next_id.set(next_id.get() + 1);
+        Ok(Session { id: next_id.get(), disposed: sessions.clone() })
+    });
+    assert_eq!(parent.default::<Session>().unwrap().id, 1);
+
+    {
+        let child = parent.child();
+        assert_eq!(child.default::<Session>().unwrap().id, 2);
+        assert_eq!(child.default::<Session>().unwrap().id, 2);
+        assert!(disposed.borrow().is_empty());
+    }
+    assert_eq!(*disposed.borrow(), [2]);
+    assert_eq!(parent.default::<Session>().unwrap().id, 1);
+
+    drop(parent);
+    assert_eq!(*disposed.borrow(), [2, 1]);
+}
+
+#[test]
+fn pure_resolution_reruns_factories_until_warmed_up() {
+    let container = Container::new();
+    container.pure_resolution();
+    let runs = register_counter(&container);
+
+    assert_eq!(container.default::<Counter>().unwrap().value, 1);
+    assert_eq!(container.default::<Counter>().unwrap().value, 2);
+    assert!(!container.is_resolved::<Counter>());
+
+    container.warmup::<Counter>().unwrap();
+    assert_eq!(container.default::<Counter>().unwrap().value, 3);
+    assert_eq!(container.default::<Counter>().unwrap().value, 3);
+    container.set_cached(Counter { value: 10 }).unwrap();
+    assert_eq!(container.default::<Counter>().unwrap().value, 10);
+    assert_eq!(runs.get(), 3);
+}
+
+#[test]
+fn take_instance_removes_the_registration() {
+    let container = Container::new();
+    container.register_factory(|_container: &Container| -> ContainerResult<Arc<Counter>> { Ok(Arc::new(Counter { value: 1 })) });
+    assert!(matches!(container.take_instance::<Arc<Counter>>(), Err(ContainerError::MissingEntry { type_name: _ })));
+
+    container.default::<Arc<Counter>>().unwrap();
+    let counter = container.take_instance::<Arc<Counter>>().unwrap();
+    assert_eq!(counter.value, 1);
+    assert!(!container.contains::<Arc<Counter>>());
+    assert!(matches!(container.default::<Arc<Counter>>(), Err(ContainerError::MissingEntry { type_name: _ })));
+}
+
+#[test]
+fn weak_handle_stops_upgrading_once_the_singleton_is_taken() {
+    let container = Container::new();
+    container.register_factory(|_container: &Container| -> ContainerResult<Arc<Counter>> { Ok(Arc::new(Counter { value: 1 })) });
+
+    let weak = container.default_weak::<Counter>().unwrap();
+    assert_eq!(weak.upgrade().unwrap().value, 1);
+    drop(container.take_instance::<Arc<Counter>>().unwrap());
+    assert!(weak.upgrade().is_none());
+}
+
+#[test]
+fn lifetime_of_reports_each_registration() {
+    let container = Container::new();
+    register_counter(&container);
+    container
+        .register::<Label>()
+        .as_transient()
+        .from_factory(|_container: &Container| -> ContainerResult<Label> { Ok(Label { text: String::new() }) })
+        .build();
+    container.register_scoped(|_container: &Container| -> ContainerResult<Session> {
+        Ok(Session { id: 0, disposed: Rc::new(RefCell::new(Vec::new())) })
+    });
+
+    assert_eq!(container.lifetime_of::<Counter>(), Some(Lifetime::Singleton));
+    assert_eq!(container.lifetime_of::<Label>(), Some(Lifetime::Transient));
+    assert_eq!(container.lifetime_of::<Session>(), Some(Lifetime::Scoped));
+    assert_eq!(container.lifetime_of::<u8>(), None);
+}
+
+#[test]
+fn evict_if_uncaches_only_matching_singletons() {
+    let container = Container::new();
+    let counter_runs = register_counter(&container);
+    container.register_factory(|_container: &Container| -> ContainerResult<Label> { Ok(Label { text: String::from("label") }) });
+    container.default::<Counter>().unwrap();
+    container.default::<Label>().unwrap();
+    assert_eq!(container.cached_count(), 2);
+
+    container.evict_if(|name| name == type_name::<Counter>());
+    assert_eq!(container.cached_count(), 1);
+    assert!(!container.is_resolved::<Counter>());
+    assert!(container.is_resolved::<Label>());
+    assert_eq!(container.default::<Counter>().unwrap().value, 2);
+    assert_eq!(counter_runs.get(), 2);
+}
+
+#[test]
+fn registration_builder_supports_each_lifetime_and_decorators() {
+    let container = Container::new();
+    let runs = Rc::new(Cell::new(0));
+    let singleton_runs = runs.clone();
+    container
+        .register::<Counter>()
+        .from_factory(move |_container: &Container| -> ContainerResult<Counter> {
+            singleton_runs.set(singleton_runs.get() + 1);
+            Ok(Counter { value: singleton_runs.get() })
+        })
+        .build();
+    container
+        .register::<Label>()
+        .as_transient()
+        .from_factory(|container: &Container| -> ContainerResult<Label> { Ok(Label { text: container.default::<Counter>()?.value.to_string() }) })
+        .with_decorator(|label: Label| Label { text: format!("<{}>", label.text) })
+        .with_decorator(|label: Label| Label { text: format!("[{}]", label.text) })
+        .build();
+
+    assert_eq!(container.default::<Counter>().unwrap().value, 1);
+    assert_eq!(container.default::<Counter>().unwrap().value, 1);
+    assert_eq!(container.default::<Label>().unwrap().text, "[<1>]");
+    assert_eq!(container.lifetime_of::<Label>(), Some(Lifetime::Transient));
+    assert_eq!(runs.get(), 1);
+}
+
+#[test]
+fn registration_builder_as_singleton_overrides_an_earlier_lifetime() {
+    let container = Container::new();
+    let runs = Rc::new(Cell::new(0));
+    let factory_runs = runs.clone();
+    container
+        .register::<Counter>()
+        .as_transient()
+        .as_singleton()
+        .from_factory(move |_container: &Container| -> ContainerResult<Counter> {
+            factory_runs.set(factory_runs.get() + 1);
+            Ok(Counter { value: factory_runs.get() })
+        })
+        .build();
+
+    assert_eq!(container.lifetime_of::<Counter>(), Some(Lifetime::Singleton));
+    assert_eq!(container.default::<Counter>().unwrap().value, 1);
+    assert_eq!(container.default::<Counter>().unwrap().value, 1);
+    assert_eq!(runs.get(), 1);
+}
+
+#[test]
+fn registration_builder_as_scoped_builds_one_instance_per_child() {
+    let parent = Container::new();
+    let runs = Rc::new(Cell::new(0));
+    let factory_runs = runs.clone();
+    parent
+        .register::<Counter>()
+        .as_scoped()
+        .from_factory(move |_container: &Container| -> ContainerResult<Counter> {
+            factory_runs.set(factory_runs.get() + 1);
+            Ok(Counter { value: factory_runs.get() })
+        })
+        .build();
+
+    assert_eq!(parent.lifetime_of::<Counter>(), Some(Lifetime::Scoped));
+    assert_eq!(parent.default::<Counter>().unwrap().value, 1);
+    let child = parent.child();
+    assert_eq!(child.default::<Counter>().unwrap().value, 2);
+    assert_eq!(child.default::<Counter>().unwrap().value, 2);
+    assert_eq!(parent.default::<Counter>().unwrap().value, 1);
+    assert_eq!(runs.get(), 2);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn memoized_factory_runs_once_per_argument() {
+    let container = Container::new();
+    let runs = Rc::new(Cell::new(0));
+    let factory_runs = runs.clone();
+    container.register_memoized(move |_container: &Container, text: &String| -> ContainerResult<Label> {
+        factory_runs.set(factory_runs.get() + 1);
+        Ok(Label { text: text.to_uppercase() })
+    });
+
+    assert_eq!(container.resolve_memoized::<Label, String>(String::from("a")).unwrap().text, "A");
+    assert_eq!(container.resolve_memoized::<Label, String>(String::from("a")).unwrap().text, "A");
+    assert_eq!(runs.get(), 1);
+    assert_eq!(container.resolve_memoized::<Label, String>(String::from("b")).unwrap().text, "B");
+    assert_eq!(runs.get(), 2);
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "log")]
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use rust_container::{Container, ContainerResult};
+use std::any::type_name;
+use std::sync::Mutex;
+
+struct CapturingLogger {
+    lines: Mutex<Vec<(Level, String)>>
+}
+
+impl Log for CapturingLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        self.lines.lock().unwrap().push((record.level(), record.args().to_string()));
+    }
+
+    fn flush(&self) { }
+}
+
+static LOGGER: CapturingLogger = CapturingLogger { lines: Mutex::new(Vec::new()) };
+
+#[derive(Clone)]
+struct Inventory;
+
+#[derive(Clone)]
+struct Checkout;
+
+// The logger is global, so everything is checked from a single test.
+#[test]
+fn registration_and_resolution_emit_log_lines() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let container = Container::new();
+    container.register_factory(|_container: &Container| -> ContainerResult<Inventory> { Ok(Inventory) });
+    container.default::<Inventory>().unwrap();
+    container.default::<Inventory>().unwrap();
+    container.register_factory_named_type("checkout", |_container: &Container| -> ContainerResult<Checkout> { Ok(Checkout) });
+    container.disable::<Checkout>();
+    assert!(container.default::<Checkout>().is_err());
+
+    let lines = LOGGER.lines.lock().unwrap();
+    let logged = |level: Level, message: String| lines.iter().any(|(line_level, line)| *line_level == level && *line == message);
+    assert!(logged(Level::Debug, format!("registering factory for {}", type_name::<Inventory>())));
+    assert!(logged(Level::Debug, format!("running factory for {}", type_name::<Inventory>())));
+    assert!(logged(Level::Trace, format!("cache hit for {}", type_name::<Inventory>())));
+    assert!(logged(Level::Debug, String::from("missing entry for checkout")));
+}
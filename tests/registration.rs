@@ -0,0 +1,173 @@
+#![allow(clippy::arc_with_non_send_sync)]
+
+use rust_container::{Container, ContainerBuilder, ContainerError, ContainerResult, Dispose};
+use std::any::{Any, TypeId};
+use std::rc::Rc;
+use std::sync::Arc;
+
+trait Greeter {
+    fn greet(&self) -> String;
+}
+
+struct Polite;
+
+impl Greeter for Polite {
+    fn greet(&self) -> String {
+        String::from("good morning")
+    }
+}
+
+struct Casual;
+
+impl Greeter for Casual {
+    fn greet(&self) -> String {
+        String::from("hey")
+    }
+}
+
+fn greeting(container: &Container) -> String {
+    container.default::<Arc<dyn Greeter>>().unwrap().greet()
+}
+
+#[test]
+fn activated_profile_selects_its_registrations() {
+    for (profile, expected) in [("test", "hey"), ("prod", "good morning")].iter() {
+        let container = Container::new();
+        container
+            .register_profile("test", |container: &Container| {
+                container.register_factory(|_container: &Container| -> ContainerResult<Arc<dyn Greeter>> { Ok(Arc::new(Casual)) });
+            })
+            .register_profile("prod", |container: &Container| {
+                container.register_factory(|_container: &Container| -> ContainerResult<Arc<dyn Greeter>> { Ok(Arc::new(Polite)) });
+            });
+        assert!(container.default::<Arc<dyn Greeter>>().is_err());
+
+        assert!(container.activate_profile(profile));
+        assert_eq!(greeting(&container), *expected);
+        assert!(!container.activate_profile(profile));
+    }
+}
+
+#[test]
+fn activated_profile_keeps_overrides_disabled_entries_and_hooks() {
+    let container = Container::new();
+    container
+        .register_factory(|_container: &Container| -> ContainerResult<Arc<dyn Greeter>> { Ok(Arc::new(Polite)) })
+        .register_instance(7u8)
+        .register_profile("test", |container: &Container| {
+            container
+                .push_override(|_container: &Container| -> ContainerResult<Arc<dyn Greeter>> { Ok(Arc::new(Casual)) })
+                .register_instance(7u8)
+                .set_fallback_resolver(|type_id: TypeId| -> Option<Arc<dyn Any>> {
+                    if type_id == TypeId::of::<u16>() { Some(Arc::new(16u16)) } else { None }
+                });
+            container.disable::<u8>();
+        });
+    container.activate_profile("test");
+
+    assert_eq!(greeting(&container), "hey");
+    assert!(matches!(container.default::<u8>(), Err(ContainerError::MissingEntry { type_name: _ })));
+    assert_eq!(container.default::<u16>().unwrap(), 16);
+
+    assert!(container.pop_override::<Arc<dyn Greeter>>());
+    assert_eq!(greeting(&container), "good morning");
+}
+
+#[derive(Clone)]
+struct Session;
+
+impl Dispose for Session {
+    fn dispose(&self) { }
+}
+
+#[test]
+fn validate_reports_missing_requirements_without_running_factories() {
+    let container = Container::new();
+    container
+        .register_factory(|container: &Container| -> ContainerResult<Arc<dyn Greeter>> {
+            container.default::<u32>()?;
+            Ok(Arc::new(Polite))
+        })
+        .require::<Arc<dyn Greeter>>()
+        .require::<u32>()
+        .require::<u64>();
+
+    match container.validate() {
+        Err(ContainerError::UnsatisfiedRequirements { type_names }) => assert_eq!(type_names, ["u32", "u64"]),
+        other => panic!("unexpected validation result {:?}", other)
+    }
+    assert_eq!(container.resolution_count::<Arc<dyn Greeter>>(), 0);
+}
+
+#[test]
+fn validate_accepts_requirements_met_anywhere_resolution_looks() {
+    let parent = Container::new();
+    parent
+        .register_instance(1u8)
+        .register_scoped(|_container: &Container| -> ContainerResult<Session> { Ok(Session) });
+    let child = parent.child();
+    child
+        .register_named_instance("answer", 42u16)
+        .register_specialized_factory(1i32, |_container: &Container| -> ContainerResult<u32> { Ok(1) })
+        .register_rc(Rc::new(7u64))
+        .require::<u8>()
+        .require::<Session>()
+        .require::<u16>()
+        .require::<u32>()
+        .require::<u64>();
+
+    assert!(child.validate().is_ok());
+}
+
+#[test]
+fn build_validated_reports_each_missing_requirement() {
+    let errors = ContainerBuilder::new()
+        .configure(|container: &Container| {
+            container.register_instance(1u8);
+        })
+        .require::<u8>()
+        .require::<u16>()
+        .require::<u32>()
+        .build_validated()
+        .unwrap_err();
+
+    let type_names: Vec<Vec<String>> = errors.into_iter()
+        .map(|err| match err {
+            ContainerError::UnsatisfiedRequirements { type_names } => type_names,
+            other => panic!("unexpected error {:?}", other)
+        })
+        .collect();
+    assert_eq!(type_names, [["u16"], ["u32"]]);
+}
+
+#[test]
+fn build_validated_builds_a_complete_container() {
+    let container = ContainerBuilder::new()
+        .configure(|container: &Container| {
+            container.register_instance(1u8);
+        })
+        .require::<u8>()
+        .build_validated()
+        .unwrap();
+
+    assert_eq!(container.default::<u8>().unwrap(), 1);
+}
+
+#[test]
+fn try_register_instance_refuses_to_replace_a_resolved_singleton() {
+    for separate_cache in [false, true].iter() {
+        let container = Container::new();
+        if *separate_cache {
+            container.cache_separately();
+        }
+        container.register_factory(|_container: &Container| -> ContainerResult<u32> { Ok(1) });
+
+        assert!(container.try_register_instance(2u32).is_ok());
+        assert_eq!(container.default::<u32>().unwrap(), 2);
+
+        container.register_factory(|_container: &Container| -> ContainerResult<u32> { Ok(3) });
+        assert_eq!(container.default::<u32>().unwrap(), 3);
+        assert!(matches!(container.try_register_instance(4u32), Err(ContainerError::AlreadyResolved)));
+        assert_eq!(container.default::<u32>().unwrap(), 3);
+    }
+}
@@ -0,0 +1,730 @@
+#![allow(clippy::arc_with_non_send_sync)]
+
+use rust_container::{Container, ContainerError, ContainerResult, Dispose, InstanceCow, Lifetime, Phase, Resolver};
+use std::any::{type_name, Any, TypeId};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::Arc;
+
+#[derive(Clone, Debug, PartialEq)]
+struct Config {
+    retries: u32
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Service {
+    retries: u32
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Session {
+    id: u32
+}
+
+impl Dispose for Session {
+    fn dispose(&self) { }
+}
+
+fn register_service(container: &Container, runs: Rc<Cell<usize>>) {
+    container
+        .register_instance(Config { retries: 3 })
+        .register_factory(move |container: &Container| -> ContainerResult<Service> {
+            runs.set(runs.get() + 1);
+            let config: Config = container.default()?;
+            Ok(Service { retries: config.retries })
+        });
+}
+
+#[test]
+fn singleton_factory_runs_once() {
+    let container = Container::new();
+    let runs = Rc::new(Cell::new(0));
+    register_service(&container, runs.clone());
+
+    assert_eq!(container.default::<Service>().unwrap(), Service { retries: 3 });
+    assert_eq!(container.default::<Service>().unwrap(), Service { retries: 3 });
+    assert_eq!(runs.get(), 1);
+}
+
+#[test]
+fn disabled_entry_is_missing_until_enabled() {
+    let container = Container::new();
+    register_service(&container, Rc::new(Cell::new(0)));
+    assert!(container.default::<Service>().is_ok());
+
+    assert!(container.disable::<Service>());
+    assert!(matches!(container.default::<Service>(), Err(ContainerError::MissingEntry { type_name: _ })));
+
+    assert!(container.enable::<Service>());
+    assert_eq!(container.default::<Service>().unwrap(), Service { retries: 3 });
+}
+
+#[test]
+fn disabled_entry_falls_through_to_parent() {
+    let parent = Container::new();
+    parent.register_instance(Config { retries: 1 });
+    let child = parent.child();
+    child.register_instance(Config { retries: 2 });
+
+    child.disable::<Config>();
+    assert_eq!(child.default::<Config>().unwrap(), Config { retries: 1 });
+    child.enable::<Config>();
+    assert_eq!(child.default::<Config>().unwrap(), Config { retries: 2 });
+}
+
+#[test]
+fn disabled_entry_is_absent_from_borrowing_paths() {
+    let container = Container::new();
+    container.register_instance(Config { retries: 3 });
+    container.disable::<Config>();
+
+    assert!(!container.is_resolved::<Config>());
+    assert!(container.borrow_instance::<Config>().is_err());
+    assert!(container.take_instance::<Config>().is_err());
+    let mut slot = Config { retries: 0 };
+    assert!(matches!(container.resolve_into(&mut slot), Err(ContainerError::MissingEntry { type_name: _ })));
+    assert!(container.default_cow::<Config>().is_err());
+
+    container.enable::<Config>();
+    container.resolve_into(&mut slot).unwrap();
+    assert_eq!(slot, Config { retries: 3 });
+}
+
+#[test]
+fn disabled_entry_is_not_a_present_prerequisite() {
+    let container = Container::new();
+    container.register_instance(Config { retries: 3 });
+    container.disable::<Config>();
+    assert!(!container.contains::<Config>());
+
+    container.register_factory_when_present::<Config, Service, _>(|container: &Container| -> ContainerResult<Service> {
+        Ok(Service { retries: container.default::<Config>()?.retries })
+    });
+    assert!(!container.contains::<Service>());
+
+    container.enable::<Config>();
+    assert!(container.contains::<Config>());
+}
+
+#[test]
+fn default_tracked_reports_whether_a_factory_ran() {
+    let container = Container::new();
+    register_service(&container, Rc::new(Cell::new(0)));
+
+    assert_eq!(container.default_tracked::<Service>().unwrap(), (Service { retries: 3 }, true));
+    assert_eq!(container.default_tracked::<Service>().unwrap(), (Service { retries: 3 }, false));
+    assert_eq!(container.default_tracked::<Config>().unwrap(), (Config { retries: 3 }, false));
+}
+
+#[test]
+fn default_tracked_counts_factories_run_by_a_parent_or_scope() {
+    let parent = Container::new();
+    register_service(&parent, Rc::new(Cell::new(0)));
+    parent.register_scoped(|_container: &Container| -> ContainerResult<Session> { Ok(Session { id: 5 }) });
+    let child = parent.child();
+
+    assert!(child.default_tracked::<Service>().unwrap().1);
+    assert!(!child.default_tracked::<Service>().unwrap().1);
+    assert!(child.default_tracked::<Session>().unwrap().1);
+    assert!(!child.default_tracked::<Session>().unwrap().1);
+}
+
+#[test]
+fn default_tracked_is_false_for_intercepted_values() {
+    let container = Container::new();
+    register_service(&container, Rc::new(Cell::new(0)));
+    container.set_pre_resolve_interceptor(|type_id: TypeId| -> Option<Arc<dyn Any>> {
+        if type_id == TypeId::of::<Service>() { Some(Arc::new(Service { retries: 9 })) } else { None }
+    });
+
+    assert_eq!(container.default_tracked::<Service>().unwrap(), (Service { retries: 9 }, false));
+}
+
+#[test]
+fn pre_resolve_interceptor_overrides_a_registered_factory_on_every_path() {
+    let container = Container::new();
+    let runs = Rc::new(Cell::new(0));
+    register_service(&container, runs.clone());
+    container.default::<Service>().unwrap();
+    container.set_pre_resolve_interceptor(|type_id: TypeId| -> Option<Arc<dyn Any>> {
+        if type_id == TypeId::of::<Service>() { Some(Arc::new(Service { retries: 9 })) } else { None }
+    });
+
+    assert_eq!(container.default::<Service>().unwrap(), Service { retries: 9 });
+    assert_eq!(container.default_cow::<Service>().unwrap().into_owned(), Service { retries: 9 });
+    let mut slot = Service { retries: 0 };
+    container.resolve_into(&mut slot).unwrap();
+    assert_eq!(slot, Service { retries: 9 });
+    assert_eq!(container.default_cow::<Config>().unwrap().into_owned(), Config { retries: 3 });
+    assert_eq!(runs.get(), 1);
+}
+
+#[test]
+fn borrow_instance_reads_without_cloning() {
+    let container = Container::new();
+    container.register_instance(Config { retries: 3 });
+
+    let config = container.borrow_instance::<Config>().unwrap();
+    assert_eq!(config.retries, 3);
+    drop(config);
+    assert!(matches!(container.borrow_instance::<Service>(), Err(ContainerError::MissingEntry { type_name: _ })));
+}
+
+#[test]
+fn retrying_factory_stops_after_the_first_success() {
+    let container = Container::new();
+    let attempts = Rc::new(Cell::new(0));
+    let attempts_made = attempts.clone();
+    container.register_factory_with_retry(3, move |_container: &Container| -> ContainerResult<Config> {
+        attempts_made.set(attempts_made.get() + 1);
+        if attempts_made.get() < 3 {
+            return Err(ContainerError::DowncastFailure);
+        }
+        Ok(Config { retries: attempts_made.get() })
+    });
+
+    assert_eq!(container.default::<Config>().unwrap(), Config { retries: 3 });
+    assert_eq!(attempts.get(), 3);
+    container.default::<Config>().unwrap();
+    assert_eq!(attempts.get(), 3);
+}
+
+#[test]
+fn retrying_factory_gives_up_after_its_attempts() {
+    let container = Container::new();
+    let attempts = Rc::new(Cell::new(0));
+    let attempts_made = attempts.clone();
+    container.register_factory_with_retry(2, move |_container: &Container| -> ContainerResult<Config> {
+        attempts_made.set(attempts_made.get() + 1);
+        Err(ContainerError::DowncastFailure)
+    });
+
+    assert!(container.default::<Config>().is_err());
+    assert_eq!(attempts.get(), 2);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn retrying_factory_backs_off_between_attempts() {
+    use std::cell::RefCell;
+    use std::time::{Duration, Instant};
+
+    let container = Container::new();
+    let attempted_at = Rc::new(RefCell::new(Vec::new()));
+    let attempts_made = attempted_at.clone();
+    container.register_factory_with_retry_backoff(3, Duration::from_millis(10), move |_container: &Container| -> ContainerResult<Config> {
+        attempts_made.borrow_mut().push(Instant::now());
+        if attempts_made.borrow().len() < 3 {
+            return Err(ContainerError::DowncastFailure);
+        }
+        Ok(Config { retries: 3 })
+    });
+
+    assert_eq!(container.default::<Config>().unwrap(), Config { retries: 3 });
+    let attempted_at = attempted_at.borrow();
+    assert_eq!(attempted_at.len(), 3);
+    for attempts in attempted_at.windows(2) {
+        assert!(attempts[1] - attempts[0] >= Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn is_resolved_turns_true_once_the_factory_ran() {
+    let container = Container::new();
+    register_service(&container, Rc::new(Cell::new(0)));
+
+    assert!(container.is_resolved::<Config>());
+    assert!(!container.is_resolved::<Service>());
+    container.default::<Service>().unwrap();
+    assert!(container.is_resolved::<Service>());
+}
+
+#[test]
+fn strict_phases_reject_resolution_until_resolving_begins() {
+    let container = Container::new();
+    container.strict_phases();
+    register_service(&container, Rc::new(Cell::new(0)));
+
+    assert_eq!(container.phase(), Phase::Registering);
+    assert!(matches!(container.default::<Service>(), Err(ContainerError::WrongPhase { phase: Phase::Registering })));
+    container.begin_resolving();
+    assert_eq!(container.phase(), Phase::Resolving);
+    assert_eq!(container.default::<Service>().unwrap(), Service { retries: 3 });
+}
+
+#[test]
+fn resolve_into_overwrites_an_existing_value() {
+    let container = Container::new();
+    register_service(&container, Rc::new(Cell::new(0)));
+
+    let mut service = Service { retries: 0 };
+    container.resolve_into(&mut service).unwrap();
+    assert_eq!(service, Service { retries: 3 });
+    let mut config = Config { retries: 0 };
+    container.resolve_into(&mut config).unwrap();
+    assert_eq!(config, Config { retries: 3 });
+}
+
+#[test]
+fn context_is_visible_only_to_its_resolution() {
+    let container = Container::new();
+    container.register_factory(|container: &Container| -> ContainerResult<Service> {
+        let config: Config = container.default()?;
+        Ok(Service { retries: config.retries })
+    });
+
+    assert_eq!(container.default_with_context::<Service, Config>(Config { retries: 7 }).unwrap(), Service { retries: 7 });
+    assert_eq!(container.default_with_context::<Service, Config>(Config { retries: 8 }).unwrap(), Service { retries: 8 });
+    assert!(matches!(container.default::<Service>(), Err(ContainerError::MissingEntry { type_name: _ })));
+}
+
+#[test]
+fn resolve_first_returns_the_first_candidate_that_resolves() {
+    let container = Container::new();
+    container.register_instance(Config { retries: 2 });
+    let candidates: [Resolver<u32>; 2] = [
+        Box::new(|container: &Container| -> ContainerResult<u32> { Ok(container.default::<Service>()?.retries) }),
+        Box::new(|container: &Container| -> ContainerResult<u32> { Ok(container.default::<Config>()?.retries) })
+    ];
+
+    assert_eq!(container.resolve_first(&candidates).unwrap(), 2);
+    assert!(matches!(container.resolve_first(&candidates[..1]), Err(ContainerError::MissingEntry { type_name: _ })));
+}
+
+trait Retrying {
+    fn retries(&self) -> u32;
+}
+
+impl Retrying for Config {
+    fn retries(&self) -> u32 {
+        self.retries
+    }
+}
+
+#[test]
+fn resolve_first_skips_every_kind_of_missing_candidate() {
+    let container = Container::new();
+    container
+        .register_instance(Config { retries: 2 })
+        .register_instance(Arc::new(Config { retries: 4 }))
+        .witness_trait(|config: Arc<Config>| -> Arc<dyn Retrying> { config });
+    let candidates: [Resolver<u32>; 4] = [
+        Box::new(|container: &Container| -> ContainerResult<u32> { Ok(container.named::<Config>("primary")?.retries) }),
+        Box::new(|container: &Container| -> ContainerResult<u32> { Ok(container.specialized::<Service, i32>(1)?.retries) }),
+        Box::new(|container: &Container| -> ContainerResult<u32> { Ok(container.default::<Arc<dyn Retrying>>()?.retries()) }),
+        Box::new(|container: &Container| -> ContainerResult<u32> { Ok(container.default::<Config>()?.retries) })
+    ];
+
+    assert_eq!(container.resolve_first(&candidates).unwrap(), 2);
+    assert!(matches!(container.resolve_first(&candidates[..1]), Err(ContainerError::MissingNamedEntry { type_name: _ })));
+    assert!(matches!(container.resolve_first(&candidates[..3]), Err(ContainerError::NotRegisteredAsTrait { type_name: _, implementors: _ })));
+}
+
+#[test]
+fn resolve_shared_hands_out_the_same_allocation() {
+    let container = Container::new();
+    container.register_arc(Arc::new(Config { retries: 3 }));
+
+    let first = container.resolve_shared::<Config>().unwrap();
+    let second = container.resolve_shared::<Config>().unwrap();
+    assert!(Arc::ptr_eq(&first, &second));
+    assert_eq!(*first, Config { retries: 3 });
+}
+
+#[test]
+fn resolution_budget_limits_factory_runs_per_resolution() {
+    let container = Container::new();
+    container
+        .register_factory_configurable(|_container: &Container| -> ContainerResult<Config> { Ok(Config { retries: 1 }) })
+        .set_lifetime(Lifetime::Transient);
+    container.register_factory(|container: &Container| -> ContainerResult<Service> {
+        let mut retries = 0;
+        for _ in 0..5 {
+            retries += container.default::<Config>()?.retries;
+        }
+        Ok(Service { retries })
+    });
+
+    container.set_resolution_budget(3);
+    assert!(matches!(container.default::<Service>(), Err(ContainerError::BudgetExceeded)));
+    container.set_resolution_budget(6);
+    assert_eq!(container.default::<Service>().unwrap(), Service { retries: 5 });
+}
+
+fn register_service_and_session(child: &Container) {
+    child.register_factory(|container: &Container| -> ContainerResult<(Service, Session)> {
+        Ok((container.default()?, container.default()?))
+    });
+}
+
+#[test]
+fn parent_budget_counts_factories_run_for_a_child() {
+    let parent = Container::new();
+    register_service(&parent, Rc::new(Cell::new(0)));
+    parent.register_factory(|_container: &Container| -> ContainerResult<Session> { Ok(Session { id: 1 }) });
+    let child = parent.child();
+    register_service_and_session(&child);
+
+    parent.set_resolution_budget(2);
+    assert!(matches!(child.default::<(Service, Session)>(), Err(ContainerError::BudgetExceeded)));
+    parent.set_resolution_budget(3);
+    assert_eq!(child.default::<(Service, Session)>().unwrap(), (Service { retries: 3 }, Session { id: 1 }));
+}
+
+#[test]
+fn child_budget_counts_factories_run_in_the_parent() {
+    let parent = Container::new();
+    register_service(&parent, Rc::new(Cell::new(0)));
+    parent.register_factory(|_container: &Container| -> ContainerResult<Session> { Ok(Session { id: 1 }) });
+    let child = parent.child();
+    register_service_and_session(&child);
+
+    child.set_resolution_budget(2);
+    assert!(matches!(child.default::<(Service, Session)>(), Err(ContainerError::BudgetExceeded)));
+    assert!(parent.default::<Session>().is_ok());
+}
+
+#[derive(Debug)]
+enum AppError {
+    Container(ContainerError)
+}
+
+impl From<ContainerError> for AppError {
+    fn from(error: ContainerError) -> Self {
+        AppError::Container(error)
+    }
+}
+
+#[test]
+fn default_as_converts_into_the_caller_error() {
+    let container = Container::new();
+    container.register_instance(Config { retries: 3 });
+
+    let config: Result<Config, AppError> = container.default_as();
+    assert_eq!(config.unwrap(), Config { retries: 3 });
+    let service: Result<Service, AppError> = container.default_as();
+    assert!(matches!(service, Err(AppError::Container(ContainerError::MissingEntry { type_name: _ }))));
+}
+
+#[test]
+fn factories_may_capture_single_threaded_state() {
+    let log: Rc<RefCell<Vec<&'static str>>> = Rc::new(RefCell::new(Vec::new()));
+    let factory_log = log.clone();
+    let container = Container::new();
+    container.register_factory(move |_container: &Container| -> ContainerResult<Rc<Config>> {
+        factory_log.borrow_mut().push("built");
+        Ok(Rc::new(Config { retries: 3 }))
+    });
+
+    assert_eq!(container.default::<Rc<Config>>().unwrap().retries, 3);
+    assert_eq!(*log.borrow(), ["built"]);
+}
+
+#[test]
+fn lazy_handle_defers_the_factory_until_get() {
+    let container = Container::new();
+    let runs = Rc::new(Cell::new(0));
+    register_service(&container, runs.clone());
+
+    let service = container.lazy::<Service>();
+    assert_eq!(runs.get(), 0);
+    assert!(!service.is_resolved());
+    assert_eq!(service.get().unwrap(), &Service { retries: 3 });
+    assert_eq!(service.get().unwrap(), &Service { retries: 3 });
+    assert!(service.is_resolved());
+    assert_eq!(runs.get(), 1);
+}
+
+#[test]
+fn factory_resolving_a_sibling_factory_does_not_hold_a_borrow() {
+    let container = Container::new();
+    container
+        .register_factory(|_container: &Container| -> ContainerResult<Config> { Ok(Config { retries: 4 }) })
+        .register_factory(|container: &Container| -> ContainerResult<Service> {
+            let config: Config = container.default()?;
+            container.register_instance(Session { id: config.retries });
+            Ok(Service { retries: config.retries })
+        });
+
+    assert_eq!(container.default::<Service>().unwrap(), Service { retries: 4 });
+    assert_eq!(container.default::<Session>().unwrap(), Session { id: 4 });
+}
+
+#[test]
+fn resolve_batch_reports_a_result_per_resolver() {
+    let container = Container::new();
+    container.register_instance(Config { retries: 3 });
+    let resolvers: [Resolver<Box<dyn Any>>; 2] = [
+        Box::new(|container: &Container| -> ContainerResult<Box<dyn Any>> { Ok(Box::new(container.default::<Config>()?)) }),
+        Box::new(|container: &Container| -> ContainerResult<Box<dyn Any>> { Ok(Box::new(container.default::<Service>()?)) })
+    ];
+
+    let results = container.resolve_batch(&resolvers);
+    assert_eq!(results[0].as_ref().unwrap().downcast_ref::<Config>(), Some(&Config { retries: 3 }));
+    assert!(matches!(results[1], Err(ContainerError::MissingEntry { type_name: _ })));
+}
+
+#[test]
+fn default_cow_borrows_stored_instances_and_owns_fresh_ones() {
+    let container = Container::new();
+    container.register_instance(Config { retries: 3 });
+    container.register_factory_configurable(|_container: &Container| -> ContainerResult<Service> { Ok(Service { retries: 1 }) })
+        .set_lifetime(Lifetime::Transient);
+
+    match container.default_cow::<Config>().unwrap() {
+        InstanceCow::Borrowed(config) => assert_eq!(*config, Config { retries: 3 }),
+        InstanceCow::Owned(_) => panic!("expected a borrowed instance")
+    }
+    match container.default_cow::<Service>().unwrap() {
+        InstanceCow::Owned(service) => assert_eq!(service, Service { retries: 1 }),
+        InstanceCow::Borrowed(_) => panic!("expected an owned instance")
+    };
+}
+
+#[test]
+fn instance_try_converts_the_raw_value_on_resolution() {
+    let container = Container::new();
+    container
+        .register_instance_try(String::from("3"), |raw: String| -> ContainerResult<Config> {
+            Ok(Config { retries: raw.parse().map_err(|_| ContainerError::DowncastFailure)? })
+        })
+        .register_instance_try(String::from("three"), |raw: String| -> ContainerResult<Service> {
+            match raw.parse() {
+                Ok(retries) => Ok(Service { retries }),
+                Err(error) => Err(ContainerError::FactoryError { error: Box::new(error) })
+            }
+        });
+
+    assert_eq!(container.default::<Config>().unwrap(), Config { retries: 3 });
+    assert!(container.default::<Service>().unwrap_err().is_factory_error());
+}
+
+#[test]
+fn fallback_resolver_supplies_unregistered_types() {
+    let container = Container::new();
+    container.set_fallback_resolver(|type_id: TypeId| -> Option<Arc<dyn Any>> {
+        if type_id == TypeId::of::<Config>() { Some(Arc::new(Config { retries: 6 })) } else { None }
+    });
+
+    assert_eq!(container.default::<Config>().unwrap(), Config { retries: 6 });
+    assert!(matches!(container.default::<Service>(), Err(ContainerError::MissingEntry { type_name: _ })));
+}
+
+#[test]
+fn default_ref_stays_valid_across_other_resolutions() {
+    let container = Container::new();
+    register_service(&container, Rc::new(Cell::new(0)));
+
+    let config: &Config = container.default_ref().unwrap();
+    let service: &Service = container.default_ref().unwrap();
+    container.register_instance(Config { retries: 9 });
+    container.reset_cache();
+    container.default::<Service>().unwrap();
+    assert_eq!(*config, Config { retries: 3 });
+    assert_eq!(*service, Service { retries: 3 });
+    assert_eq!(container.default::<Config>().unwrap(), Config { retries: 9 });
+}
+
+#[test]
+fn on_missing_handler_registers_the_entry_for_a_retry() {
+    let container = Container::new();
+    container.set_on_missing(|container: &Container, type_id: TypeId| {
+        if type_id == TypeId::of::<Config>() {
+            container.register_instance(Config { retries: 5 });
+        }
+    });
+
+    assert_eq!(container.default::<Config>().unwrap(), Config { retries: 5 });
+    assert!(container.contains::<Config>());
+    assert!(matches!(container.default::<Service>(), Err(ContainerError::MissingEntry { type_name: _ })));
+}
+
+struct Level<const N: usize>;
+
+impl<const N: usize> Clone for Level<N> {
+    fn clone(&self) -> Self {
+        Level
+    }
+}
+
+fn register_levels(container: &Container) {
+    container
+        .register_factory(|_container: &Container| -> ContainerResult<Level<1>> { Ok(Level) })
+        .register_factory(|container: &Container| -> ContainerResult<Level<2>> { container.default::<Level<1>>().map(|_| Level) })
+        .register_factory(|container: &Container| -> ContainerResult<Level<3>> { container.default::<Level<2>>().map(|_| Level) })
+        .register_factory(|container: &Container| -> ContainerResult<Level<4>> { container.default::<Level<3>>().map(|_| Level) });
+}
+
+#[test]
+fn max_resolution_depth_tracks_nested_factories() {
+    let container = Container::new();
+    register_levels(&container);
+    assert_eq!(container.max_resolution_depth(), 0);
+
+    container.default::<Level<4>>().unwrap();
+    assert_eq!(container.max_resolution_depth(), 4);
+}
+
+#[test]
+fn exceeding_the_depth_limit_fails() {
+    let container = Container::new();
+    register_levels(&container);
+    container.set_max_depth(3);
+
+    assert!(matches!(container.default::<Level<4>>(), Err(ContainerError::DepthExceeded)));
+    assert!(container.default::<Level<3>>().is_ok());
+}
+
+fn register_child_level(child: &Container) {
+    child.register_factory(|container: &Container| -> ContainerResult<Level<4>> { container.default::<Level<3>>().map(|_| Level) });
+}
+
+#[test]
+fn max_resolution_depth_counts_factories_run_in_the_parent() {
+    let parent = Container::new();
+    register_levels(&parent);
+    let child = parent.child();
+    register_child_level(&child);
+
+    child.default::<Level<4>>().unwrap();
+    assert_eq!(child.max_resolution_depth(), 4);
+    assert_eq!(parent.max_resolution_depth(), 4);
+}
+
+#[test]
+fn depth_limit_spans_a_child_and_its_parent() {
+    let parent = Container::new();
+    register_levels(&parent);
+    parent.set_max_depth(3);
+    let child = parent.child();
+    register_child_level(&child);
+
+    assert!(matches!(child.default::<Level<4>>(), Err(ContainerError::DepthExceeded)));
+    let limited_child = parent.child();
+    register_child_level(&limited_child);
+    limited_child.set_max_depth(1);
+    assert!(matches!(limited_child.default::<Level<4>>(), Err(ContainerError::DepthExceeded)));
+    assert!(parent.default::<Level<3>>().is_ok());
+}
+
+#[test]
+fn resolve_any_named_labels_the_value() {
+    let container = Container::new();
+    container.register_instance(Config { retries: 3 });
+
+    let (instance, name) = container.resolve_any_named(TypeId::of::<Config>()).unwrap();
+    assert_eq!(name, type_name::<Config>());
+    assert_eq!(instance.downcast_ref::<Config>(), Some(&Config { retries: 3 }));
+    assert!(matches!(container.resolve_any_named(TypeId::of::<Service>()), Err(ContainerError::MissingEntry { type_name: _ })));
+}
+
+trait ResolveBoxed {
+    fn resolve_boxed<T: Clone + 'static>(&self) -> ContainerResult<Box<T>>;
+}
+
+impl<'container> ResolveBoxed for Container<'container> {
+    fn resolve_boxed<T: Clone + 'static>(&self) -> ContainerResult<Box<T>> {
+        let instance = self.resolve_any(TypeId::of::<T>())?;
+        match instance.downcast_ref::<T>() {
+            Some(instance) => Ok(Box::new(instance.clone())),
+            None => Err(ContainerError::DowncastFailure)
+        }
+    }
+}
+
+#[test]
+fn extension_traits_resolve_through_resolve_any() {
+    let container = Container::new();
+    register_service(&container, Rc::new(Cell::new(0)));
+
+    assert_eq!(*container.resolve_boxed::<Service>().unwrap(), Service { retries: 3 });
+    assert!(container.resolve_boxed::<Session>().unwrap_err().is_missing());
+}
+
+#[test]
+fn grandchild_resolves_through_the_whole_parent_chain() {
+    let grandparent = Container::new();
+    grandparent.register_instance(Config { retries: 3 });
+    let parent = grandparent.child();
+    let child = parent.child();
+
+    assert_eq!(child.default::<Config>().unwrap(), Config { retries: 3 });
+    assert!(std::ptr::eq(child.root(), &grandparent));
+    assert!(matches!(child.default::<Service>(), Err(ContainerError::MissingEntry { type_name: _ })));
+}
+
+#[test]
+fn rc_registrations_share_one_value() {
+    let container = Container::new();
+    let config = Rc::new(Config { retries: 3 });
+    container.register_rc(config.clone());
+
+    let first = container.resolve_rc::<Config>().unwrap();
+    let second = container.resolve_rc::<Config>().unwrap();
+    assert!(Rc::ptr_eq(&first, &second));
+    assert_eq!(Rc::strong_count(&config), 4);
+    assert!(matches!(container.resolve_rc::<Service>(), Err(ContainerError::MissingEntry { type_name: _ })));
+}
+
+#[test]
+fn named_registrations_resolve_by_name() {
+    let container = Container::new();
+    container
+        .register_named_instance("fast", Config { retries: 1 })
+        .register_named_instance("slow", Config { retries: 9 })
+        .register_named_factory("default", |_container: &Container| -> ContainerResult<Config> { Ok(Config { retries: 3 }) });
+
+    assert_eq!(container.names_for::<Config>(), ["default", "fast", "slow"]);
+    assert_eq!(container.named::<Config>("slow").unwrap(), Config { retries: 9 });
+    assert_eq!(container.named::<Config>("default").unwrap(), Config { retries: 3 });
+    assert!(matches!(container.named::<Config>("missing"), Err(ContainerError::MissingNamedEntry { type_name: _ })));
+}
+
+#[test]
+fn tagged_registrations_resolve_together() {
+    let container = Container::new();
+    for id in 1..=3 {
+        container.register_tagged("startup", move |_container: &Container| -> ContainerResult<Session> { Ok(Session { id }) });
+    }
+    container.register_tagged("shutdown", |_container: &Container| -> ContainerResult<Session> { Ok(Session { id: 4 }) });
+
+    let sessions = container.resolve_tagged::<Session>("startup").unwrap();
+    assert_eq!(sessions, [Session { id: 1 }, Session { id: 2 }, Session { id: 3 }]);
+    assert!(container.resolve_tagged::<Session>("unknown").unwrap().is_empty());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn default_timed_measures_only_factory_runs() {
+    use std::time::Duration;
+
+    let container = Container::new();
+    container.register_factory(|_container: &Container| -> ContainerResult<Config> {
+        std::thread::sleep(Duration::from_millis(20));
+        Ok(Config { retries: 1 })
+    });
+
+    assert!(container.default_timed::<Config>().unwrap().1 >= Duration::from_millis(20));
+    assert_eq!(container.default_timed::<Config>().unwrap().1, Duration::ZERO);
+}
+
+#[cfg(feature = "std")]
+mod hashing {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::BuildHasherDefault;
+
+    #[test]
+    fn custom_hasher_resolves_like_the_default() {
+        for container in [Container::new(), Container::with_hasher(BuildHasherDefault::<DefaultHasher>::default()), Container::with_capacity(16, 4)].iter() {
+            let runs = Rc::new(Cell::new(0));
+            register_service(container, runs.clone());
+
+            assert_eq!(container.default::<Service>().unwrap(), Service { retries: 3 });
+            assert_eq!(container.default::<Service>().unwrap(), Service { retries: 3 });
+            assert_eq!(runs.get(), 1);
+            assert!(container.contains::<Config>());
+            assert!(matches!(container.default::<u8>(), Err(ContainerError::MissingEntry { type_name: _ })));
+        }
+    }
+}
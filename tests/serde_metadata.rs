@@ -0,0 +1,27 @@
+#![cfg(feature = "serde")]
+
+use rust_container::{Container, ContainerMetadata, ContainerResult, EntryKind};
+use std::any::type_name;
+
+#[derive(Clone)]
+struct Inventory;
+
+#[test]
+fn metadata_round_trips_through_json() {
+    let container = Container::new();
+    container
+        .register_instance(Inventory)
+        .register_specialized_factory(2i32, |_container: &Container| -> ContainerResult<u32> { Ok(2) })
+        .register_named_instance("audit", 7u8);
+
+    let metadata = container.export_metadata();
+    let json = serde_json::to_string(&metadata).unwrap();
+    let round_tripped: ContainerMetadata = serde_json::from_str(&json).unwrap();
+    assert_eq!(round_tripped, metadata);
+
+    let kinds: Vec<(&str, EntryKind)> = round_tripped.entries.iter().map(|entry| (entry.type_name.as_str(), entry.kind)).collect();
+    assert_eq!(kinds, [(type_name::<Inventory>(), EntryKind::Default), ("u32", EntryKind::Specialized), ("u8", EntryKind::Named)]);
+    assert_eq!(round_tripped.entries[1].specialization_value, Some(2));
+    assert_eq!(round_tripped.entries[2].name.as_deref(), Some("audit"));
+    assert!(round_tripped.entries[0].resolved);
+}
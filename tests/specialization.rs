@@ -0,0 +1,329 @@
+#![allow(clippy::arc_with_non_send_sync)]
+
+use rust_container::{Container, ContainerError, ContainerResult, EntryKind};
+use std::any::type_name;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::rc::Rc;
+use std::sync::Arc;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum GroceryStoreType {
+    Organic,
+    Discount,
+    Retired
+}
+
+impl From<GroceryStoreType> for i64 {
+    fn from(grocery_store_type: GroceryStoreType) -> i64 {
+        match grocery_store_type {
+            GroceryStoreType::Organic => 0,
+            GroceryStoreType::Discount => 1,
+            GroceryStoreType::Retired => 2
+        }
+    }
+}
+
+// `Retired` stores are still registered by old wiring but can no longer be reconstructed.
+impl TryFrom<i64> for GroceryStoreType {
+    type Error = ();
+
+    fn try_from(value: i64) -> Result<Self, ()> {
+        match value {
+            0 => Ok(GroceryStoreType::Organic),
+            1 => Ok(GroceryStoreType::Discount),
+            _ => Err(())
+        }
+    }
+}
+
+// Every ten buckets share one stored value, so distinct buckets can collide.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Bucket(u8);
+
+impl From<Bucket> for i64 {
+    fn from(bucket: Bucket) -> i64 {
+        i64::from(bucket.0 / 10)
+    }
+}
+
+impl TryFrom<i64> for Bucket {
+    type Error = ();
+
+    fn try_from(value: i64) -> Result<Self, ()> {
+        u8::try_from(value * 10).map(Bucket).map_err(|_| ())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Shelf {
+    label: &'static str
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Store {
+    shelf: Shelf
+}
+
+struct Warehouse;
+
+fn register_labels(container: &Container) {
+    container
+        .register_specialized_factory(3i32, |_container: &Container| -> ContainerResult<String> { Ok(String::from("three")) })
+        .register_specialized_factory(1i32, |_container: &Container| -> ContainerResult<String> { Ok(String::from("one")) })
+        .register_specialized_factory(2i32, |_container: &Container| -> ContainerResult<String> { Ok(String::from("two")) });
+}
+
+#[test]
+fn specialized_map_is_ordered_by_specialization_value() {
+    let container = Container::new();
+    register_labels(&container);
+
+    let instances: BTreeMap<i64, String> = container.specialized_map::<String, i32>().unwrap();
+    let values: Vec<&str> = instances.values().map(String::as_str).collect();
+    assert_eq!(values, ["one", "two", "three"]);
+}
+
+#[test]
+fn specialized_map_looks_up_each_specialization_by_value() {
+    let container = Container::new();
+    register_labels(&container);
+
+    let instances = container.specialized_map::<String, i32>().unwrap();
+    assert_eq!(instances.len(), 3);
+    assert_eq!(instances[&1], "one");
+    assert_eq!(instances[&2], "two");
+    assert_eq!(instances[&3], "three");
+    assert!(!instances.contains_key(&4));
+}
+
+#[test]
+fn colliding_specializations_are_rejected() {
+    let container = Container::new();
+    container.try_register_specialized_factory(Bucket(11), |_container: &Container| -> ContainerResult<u32> { Ok(11) }).unwrap();
+    container.try_register_specialized_factory(Bucket(11), |_container: &Container| -> ContainerResult<u32> { Ok(110) }).unwrap();
+
+    let collision = container.try_register_specialized_factory(Bucket(12), |_container: &Container| -> ContainerResult<u32> { Ok(12) });
+    assert!(matches!(collision, Err(ContainerError::SpecializationCollision { specialization_value: 1 })));
+    assert_eq!(container.specialized::<u32, Bucket>(Bucket(11)).unwrap(), 110);
+}
+
+#[test]
+fn rebind_specialized_swaps_the_factory_of_a_resolved_specialization() {
+    let container = Container::new();
+    container.register_specialized_factory(GroceryStoreType::Organic, |_container: &Container| -> ContainerResult<Shelf> { Ok(Shelf { label: "old" }) });
+    assert_eq!(container.specialized::<Shelf, GroceryStoreType>(GroceryStoreType::Organic).unwrap().label, "old");
+
+    assert!(container.rebind_specialized(GroceryStoreType::Organic, |_container: &Container| -> ContainerResult<Shelf> { Ok(Shelf { label: "new" }) }));
+    assert_eq!(container.specialized::<Shelf, GroceryStoreType>(GroceryStoreType::Organic).unwrap().label, "new");
+    assert!(!container.rebind_specialized(GroceryStoreType::Discount, |_container: &Container| -> ContainerResult<Shelf> { Ok(Shelf { label: "added" }) }));
+}
+
+#[test]
+fn for_each_specialized_visits_in_value_order() {
+    let container = Container::new();
+    register_labels(&container);
+
+    let mut visited = Vec::new();
+    container.for_each_specialized::<String, i32>(|label| visited.push(label.clone())).unwrap();
+    assert_eq!(visited, ["one", "two", "three"]);
+}
+
+#[test]
+fn specialization_values_beyond_i32_resolve() {
+    let container = Container::new();
+    let large = i64::from(i32::MAX) + 1;
+    container
+        .register_specialized_factory(large, |_container: &Container| -> ContainerResult<String> { Ok(String::from("large")) })
+        .register_specialized_factory(-large, |_container: &Container| -> ContainerResult<String> { Ok(String::from("small")) });
+
+    assert_eq!(container.specialized::<String, i64>(large).unwrap(), "large");
+    assert_eq!(container.specialized::<String, i64>(-large).unwrap(), "small");
+    assert_eq!(container.all_specialized::<String, i64>().unwrap(), ["small", "large"]);
+}
+
+#[test]
+fn i32_keys_register_and_resolve() {
+    let container = Container::new();
+    container
+        .register_specialized_factory_i32(7, |_container: &Container| -> ContainerResult<String> { Ok(String::from("seven")) })
+        .register_specialized_factory_i32(-7, |_container: &Container| -> ContainerResult<String> { Ok(String::from("minus seven")) });
+
+    assert_eq!(container.specialized_i32::<String>(7).unwrap(), "seven");
+    assert_eq!(container.specialized_i32::<String>(-7).unwrap(), "minus seven");
+    assert!(matches!(container.specialized_i32::<String>(8), Err(ContainerError::MissingSpecializedEntry { type_name: _ })));
+}
+
+#[test]
+fn mixing_up_default_and_specialized_registrations_reports_the_kind() {
+    let container = Container::new();
+    container
+        .register_instance(Shelf { label: "default" })
+        .register_specialized_factory(1i32, |_container: &Container| -> ContainerResult<String> { Ok(String::from("one")) });
+
+    assert!(matches!(container.specialized::<Shelf, i32>(1),
+        Err(ContainerError::WrongEntryKind { expected: EntryKind::Specialized, found: EntryKind::Default })));
+    assert!(matches!(container.default::<String>(),
+        Err(ContainerError::WrongEntryKind { expected: EntryKind::Default, found: EntryKind::Specialized })));
+}
+
+#[test]
+fn specialized_by_resolves_the_selected_specialization() {
+    let container = Container::new();
+    register_labels(&container);
+
+    let largest: String = container.specialized_by(|specializations: &[i32]| *specializations.iter().max().unwrap()).unwrap();
+    assert_eq!(largest, "three");
+    assert!(matches!(container.specialized_by::<Shelf, i32>(|specializations: &[i32]| specializations[0]), Err(ContainerError::MissingSpecializedEntry { type_name: _ })));
+}
+
+#[test]
+fn factory_using_specialized_receives_the_specialized_dependency() {
+    let container = Container::new();
+    container
+        .register_specialized_factory(GroceryStoreType::Organic, |_container: &Container| -> ContainerResult<Shelf> { Ok(Shelf { label: "organic" }) })
+        .register_specialized_factory(GroceryStoreType::Discount, |_container: &Container| -> ContainerResult<Shelf> { Ok(Shelf { label: "discount" }) })
+        .register_factory_using_specialized(GroceryStoreType::Discount, |_container: &Container, shelf: Shelf| -> ContainerResult<Store> { Ok(Store { shelf }) });
+
+    assert_eq!(container.default::<Store>().unwrap().shelf.label, "discount");
+}
+
+#[test]
+fn specialized_or_default_falls_back_to_the_default() {
+    let container = Container::new();
+    container
+        .register_instance(Shelf { label: "default" })
+        .register_specialized_factory(GroceryStoreType::Organic, |_container: &Container| -> ContainerResult<Shelf> { Ok(Shelf { label: "organic" }) });
+
+    assert_eq!(container.specialized_or_default::<Shelf, GroceryStoreType>(GroceryStoreType::Organic).unwrap().label, "organic");
+    assert_eq!(container.specialized_or_default::<Shelf, GroceryStoreType>(GroceryStoreType::Discount).unwrap().label, "default");
+}
+
+#[test]
+fn specialized_range_collects_the_registered_keys() {
+    let container = Container::new();
+    for key in 0..3 {
+        container.register_specialized_factory_i32(key, move |_container: &Container| -> ContainerResult<i32> { Ok(key * 10) });
+    }
+
+    assert_eq!(container.specialized_range::<i32>(0..5, true).unwrap(), [0, 10, 20]);
+    assert!(matches!(container.specialized_range::<i32>(0..5, false), Err(ContainerError::MissingSpecializedEntry { type_name: _ })));
+}
+
+#[test]
+fn unreconstructible_specialization_fails_instead_of_panicking() {
+    let container = Container::new();
+    container
+        .register_specialized_factory(GroceryStoreType::Organic, |_container: &Container| -> ContainerResult<Shelf> { Ok(Shelf { label: "organic" }) })
+        .register_specialized_factory(GroceryStoreType::Retired, |_container: &Container| -> ContainerResult<Shelf> { Ok(Shelf { label: "retired" }) });
+
+    assert!(matches!(container.all_specialized::<Shelf, GroceryStoreType>(), Err(ContainerError::InvalidSpecialization { specialization_value: 2 })));
+    assert!(matches!(container.specialized_map::<Shelf, GroceryStoreType>(), Err(ContainerError::InvalidSpecialization { specialization_value: 2 })));
+}
+
+#[test]
+fn iter_specialized_resolves_only_as_far_as_it_is_driven() {
+    let runs = Rc::new(Cell::new(0));
+    let container = Container::new();
+    for key in 0..3 {
+        let runs = runs.clone();
+        container.register_specialized_factory(key, move |_container: &Container| -> ContainerResult<i32> {
+            runs.set(runs.get() + 1);
+            if key == 0 {
+                return Err(ContainerError::DowncastFailure);
+            }
+            Ok(key)
+        });
+    }
+
+    let first = container.iter_specialized::<i32, i32>().find_map(Result::ok);
+    assert_eq!(first, Some(1));
+    assert_eq!(runs.get(), 2);
+}
+
+#[test]
+fn all_specialization_keys_lists_every_pair() {
+    let container = Container::new();
+    register_labels(&container);
+    container
+        .register_specialized_factory(GroceryStoreType::Discount, |_container: &Container| -> ContainerResult<Shelf> { Ok(Shelf { label: "discount" }) })
+        .register_specialized_factory(GroceryStoreType::Organic, |_container: &Container| -> ContainerResult<Shelf> { Ok(Shelf { label: "organic" }) });
+
+    let keys = container.all_specialization_keys();
+    assert_eq!(keys.len(), 2);
+    assert!(keys.contains(&(type_name::<String>().to_string(), type_name::<i32>().to_string(), vec![1, 2, 3])));
+    assert!(keys.contains(&(type_name::<Shelf>().to_string(), type_name::<GroceryStoreType>().to_string(), vec![0, 1])));
+}
+
+#[test]
+fn all_specialized_unique_collapses_linked_instances() {
+    let container = Container::new();
+    container.register_factory(|_container: &Container| -> ContainerResult<Arc<Warehouse>> { Ok(Arc::new(Warehouse)) });
+    for key in 0..2i32 {
+        container.register_specialized_factory(key, |container: &Container| -> ContainerResult<Arc<Warehouse>> { container.default() });
+    }
+
+    assert_eq!(container.all_specialized::<Arc<Warehouse>, i32>().unwrap().len(), 2);
+    assert_eq!(container.all_specialized_unique::<Warehouse, i32>().unwrap().len(), 1);
+}
+
+#[test]
+fn try_all_specialized_reports_each_result() {
+    let container = Container::new();
+    register_labels(&container);
+    container.register_specialized_factory(4i32, |_container: &Container| -> ContainerResult<String> { Err(ContainerError::DowncastFailure) });
+
+    let results = container.try_all_specialized::<String, i32>();
+    assert_eq!(results.len(), 4);
+    let succeeded: Vec<(i32, String)> = results.iter()
+        .filter_map(|(key, result)| result.as_ref().ok().map(|label| (*key, label.clone())))
+        .collect();
+    assert_eq!(succeeded, [(1, String::from("one")), (2, String::from("two")), (3, String::from("three"))]);
+    assert!(matches!(results[3], (4, Err(ContainerError::DowncastFailure))));
+}
+
+#[test]
+fn specialized_array_resolves_one_instance_per_key() {
+    let container = Container::new();
+    register_labels(&container);
+
+    let [first, second]: [String; 2] = container.specialized_array::<String, i32, 2>([3, 1]).unwrap();
+    assert_eq!((first.as_str(), second.as_str()), ("three", "one"));
+    assert!(matches!(container.specialized_array::<String, i32, 2>([1, 5]), Err(ContainerError::MissingSpecializedEntry { type_name: _ })));
+}
+
+#[test]
+fn missing_specializations_lists_unregistered_shards() {
+    let container = Container::new();
+    container
+        .register_specialized_factory(0u8, |_container: &Container| -> ContainerResult<String> { Ok(String::from("shard 0")) })
+        .register_specialized_factory(2u8, |_container: &Container| -> ContainerResult<String> { Ok(String::from("shard 2")) });
+
+    assert_eq!(container.missing_specializations::<String, u8>(&[0, 1, 2]), [1]);
+}
+
+#[test]
+fn nested_factory_sees_the_enclosing_specialization() {
+    let container = Container::new();
+    container
+        .register::<Shelf>()
+        .as_transient()
+        .from_factory(|container: &Container| -> ContainerResult<Shelf> {
+            match container.current_specialization::<GroceryStoreType>() {
+                Some(GroceryStoreType::Organic) => Ok(Shelf { label: "kale" }),
+                Some(_) => Ok(Shelf { label: "crisps" }),
+                None => Ok(Shelf { label: "empty" })
+            }
+        })
+        .build();
+    for grocery_store_type in [GroceryStoreType::Organic, GroceryStoreType::Discount].iter() {
+        container.register_specialized_factory(*grocery_store_type, |container: &Container| -> ContainerResult<Store> {
+            Ok(Store { shelf: container.default()? })
+        });
+    }
+
+    assert_eq!(container.specialized::<Store, GroceryStoreType>(GroceryStoreType::Organic).unwrap().shelf.label, "kale");
+    assert_eq!(container.specialized::<Store, GroceryStoreType>(GroceryStoreType::Discount).unwrap().shelf.label, "crisps");
+    assert_eq!(container.default::<Shelf>().unwrap().label, "empty");
+}
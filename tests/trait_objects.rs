@@ -0,0 +1,173 @@
+#![allow(clippy::arc_with_non_send_sync)]
+
+use rust_container::{register_all_views, Container, ContainerError, ContainerResult, Resolver, SharedFn};
+use std::any::type_name;
+use std::sync::Arc;
+
+trait Fruit {
+    fn name(&self) -> &str;
+}
+
+trait Meat {
+    fn name(&self) -> &str;
+}
+
+struct Banana {
+    is_organic: bool
+}
+
+impl Fruit for Banana {
+    fn name(&self) -> &str {
+        "banana"
+    }
+}
+
+struct Apple;
+
+impl Fruit for Apple {
+    fn name(&self) -> &str {
+        "apple"
+    }
+}
+
+struct Chicken;
+
+impl Meat for Chicken {
+    fn name(&self) -> &str {
+        "chicken"
+    }
+}
+
+fn same_object<A: ?Sized, B: ?Sized>(a: &Arc<A>, b: &Arc<B>) -> bool {
+    Arc::as_ptr(a) as *const () == Arc::as_ptr(b) as *const ()
+}
+
+fn register_banana(container: &Container) {
+    container.register_factory(|_container: &Container| -> ContainerResult<Arc<Banana>> { Ok(Arc::new(Banana { is_organic: true })) });
+}
+
+#[test]
+fn shared_as_resolves_the_concrete_singleton_as_a_trait_object() {
+    let container = Container::new();
+    register_banana(&container);
+    container.register_shared_as(|banana: Arc<Banana>| -> Arc<dyn Fruit> { banana });
+
+    let fruit: Arc<dyn Fruit> = container.default().unwrap();
+    let banana: Arc<Banana> = container.default().unwrap();
+    assert!(same_object(&fruit, &banana));
+    assert!(Arc::ptr_eq(&banana, &container.default::<Arc<Banana>>().unwrap()));
+    assert!(banana.is_organic);
+    assert_eq!(fruit.name(), "banana");
+}
+
+#[test]
+fn link_concrete_to_trait_shares_the_underlying_object() {
+    let container = Container::new();
+    register_banana(&container);
+    container.link_concrete_to_trait(|banana: Arc<Banana>| -> Arc<dyn Fruit> { banana });
+
+    let banana: Arc<Banana> = container.default().unwrap();
+    let fruit: Arc<dyn Fruit> = container.default().unwrap();
+    assert!(same_object(&fruit, &banana));
+}
+
+#[test]
+fn collect_as_gathers_different_concretes_into_one_vec() {
+    let container = Container::new();
+    container
+        .register_instance(Arc::new(Banana { is_organic: false }))
+        .register_instance(Arc::new(Apple));
+    let coercions: [Resolver<Arc<dyn Fruit>>; 2] = [
+        Box::new(|container: &Container| -> ContainerResult<Arc<dyn Fruit>> { Ok(container.default::<Arc<Banana>>()?) }),
+        Box::new(|container: &Container| -> ContainerResult<Arc<dyn Fruit>> { Ok(container.default::<Arc<Apple>>()?) })
+    ];
+
+    let fruits = container.collect_as(&coercions).unwrap();
+    let names: Vec<&str> = fruits.iter().map(|fruit| fruit.name()).collect();
+    assert_eq!(names, ["banana", "apple"]);
+}
+
+#[test]
+fn resolve_concrete_recovers_the_registered_concrete() {
+    let container = Container::new();
+    register_banana(&container);
+    container.register_shared_as(|banana: Arc<Banana>| -> Arc<dyn Fruit> { banana });
+
+    let banana: Arc<Banana> = container.resolve_concrete::<dyn Fruit, Banana>().unwrap();
+    assert!(banana.is_organic);
+    assert!(matches!(container.resolve_concrete::<dyn Fruit, Apple>(), Err(ContainerError::DowncastFailure)));
+}
+
+#[test]
+fn sealed_registration_refuses_concrete_recovery() {
+    let container = Container::new();
+    register_banana(&container);
+    container.register_sealed_factory(|container: &Container| -> ContainerResult<Arc<dyn Fruit>> {
+        let banana: Arc<Banana> = container.default()?;
+        Ok(banana)
+    });
+
+    assert_eq!(container.default::<Arc<dyn Fruit>>().unwrap().name(), "banana");
+    assert!(matches!(container.resolve_concrete::<dyn Fruit, Banana>(), Err(ContainerError::DowncastFailure)));
+}
+
+#[test]
+fn function_strategies_resolve_as_callables() {
+    let container = Container::new();
+    container.register_fn(|value: i32| -> i32 { value * 2 });
+
+    let double: SharedFn<i32, i32> = container.resolve_fn().unwrap();
+    assert_eq!(double(21), 42);
+    assert!(matches!(container.resolve_fn::<i32, String>(), Err(ContainerError::MissingEntry { type_name: _ })));
+}
+
+#[test]
+fn coercions_resolve_the_whole_set() {
+    let container = Container::new();
+    container
+        .register_instance(Arc::new(Banana { is_organic: true }))
+        .register_instance(Arc::new(Apple))
+        .register_coercion(|banana: Arc<Banana>| -> Arc<dyn Fruit> { banana })
+        .register_coercion(|apple: Arc<Apple>| -> Arc<dyn Fruit> { apple });
+
+    let fruits = container.resolve_all_coerced::<dyn Fruit>().unwrap();
+    let names: Vec<&str> = fruits.iter().map(|fruit| fruit.name()).collect();
+    assert_eq!(names, ["banana", "apple"]);
+    assert!(container.resolve_all_coerced::<dyn Meat>().unwrap().is_empty());
+}
+
+#[test]
+fn dyn_factory_registers_a_trait_object() {
+    let container = Container::new();
+    container.register_dyn_factory(|_container: &Container| -> ContainerResult<Chicken> { Ok(Chicken) }, |chicken: Arc<Chicken>| -> Arc<dyn Meat> { chicken });
+
+    let meat: Arc<dyn Meat> = container.default().unwrap();
+    assert_eq!(meat.name(), "chicken");
+    assert!(Arc::ptr_eq(&meat, &container.default::<Arc<dyn Meat>>().unwrap()));
+}
+
+#[test]
+fn register_all_views_links_every_view_to_one_object() {
+    let container = Container::new();
+    register_all_views!(container, Banana { is_organic: true } => [dyn Fruit]);
+
+    let banana: Arc<Banana> = container.default().unwrap();
+    let fruit: Arc<dyn Fruit> = container.default().unwrap();
+    assert!(same_object(&fruit, &banana));
+}
+
+#[test]
+fn witnessed_implementor_is_named_when_the_trait_is_missing() {
+    let container = Container::new();
+    register_banana(&container);
+    container.witness_trait(|banana: Arc<Banana>| -> Arc<dyn Fruit> { banana });
+
+    match container.default::<Arc<dyn Fruit>>() {
+        Err(ContainerError::NotRegisteredAsTrait { type_name: missing, implementors }) => {
+            assert_eq!(missing, type_name::<Arc<dyn Fruit>>());
+            assert_eq!(implementors, [type_name::<Arc<Banana>>()]);
+        },
+        _ => panic!("expected NotRegisteredAsTrait")
+    }
+    assert!(matches!(container.default::<Arc<dyn Meat>>(), Err(ContainerError::MissingEntry { type_name: _ })));
+}
@@ -0,0 +1,228 @@
+#![allow(clippy::arc_with_non_send_sync)]
+
+use rust_container::{Container, ContainerResult, Module, OverrideSet};
+use std::any::type_name;
+use std::sync::Arc;
+
+trait Fruit {
+    fn name(&self) -> &str;
+}
+
+trait Meat {
+    fn name(&self) -> &str;
+}
+
+struct Banana;
+
+impl Fruit for Banana {
+    fn name(&self) -> &str {
+        "banana"
+    }
+}
+
+struct Chicken;
+
+impl Meat for Chicken {
+    fn name(&self) -> &str {
+        "chicken"
+    }
+}
+
+struct MockFruit;
+
+impl Fruit for MockFruit {
+    fn name(&self) -> &str {
+        "mock fruit"
+    }
+}
+
+struct MockMeat;
+
+impl Meat for MockMeat {
+    fn name(&self) -> &str {
+        "mock meat"
+    }
+}
+
+#[derive(Clone)]
+struct WholeFoods {
+    fruit: Arc<dyn Fruit>,
+    meat: Arc<dyn Meat>
+}
+
+impl WholeFoods {
+    fn new(fruit: Arc<dyn Fruit>, meat: Arc<dyn Meat>) -> Self {
+        Self {
+            fruit,
+            meat
+        }
+    }
+
+    fn inventory(&self) -> (&str, &str) {
+        (self.fruit.name(), self.meat.name())
+    }
+}
+
+fn register_fruit(container: &Container) {
+    container.register_factory(|_container: &Container| -> ContainerResult<Arc<dyn Fruit>> { Ok(Arc::new(Banana)) });
+}
+
+fn register_meat(container: &Container) {
+    container.register_factory(|_container: &Container| -> ContainerResult<Arc<dyn Meat>> { Ok(Arc::new(Chicken)) });
+}
+
+fn register_whole_foods(container: &Container) {
+    container.register_factory(|container: &Container| -> ContainerResult<WholeFoods> {
+        Ok(WholeFoods::new(container.default()?, container.default()?))
+    });
+}
+
+#[test]
+fn dependency_graph_records_whole_foods_dependencies() {
+    let container = Container::new();
+    container.record_dependencies();
+    register_fruit(&container);
+    register_meat(&container);
+    register_whole_foods(&container);
+    assert_eq!(container.default::<WholeFoods>().unwrap().inventory(), ("banana", "chicken"));
+
+    let graph = container.dependency_graph();
+    assert_eq!(graph.dependencies_of(type_name::<WholeFoods>()), [type_name::<Arc<dyn Fruit>>(), type_name::<Arc<dyn Meat>>()]);
+    assert!(graph.dependencies_of(type_name::<Arc<dyn Fruit>>()).is_empty());
+    assert!(Container::new().dependency_graph().edges().is_empty());
+}
+
+#[test]
+fn factory_when_present_depends_on_registration_order() {
+    let container = Container::new();
+    container.register_factory_when_present::<Arc<dyn Fruit>, WholeFoods, _>(|container: &Container| -> ContainerResult<WholeFoods> {
+        Ok(WholeFoods::new(container.default()?, container.default()?))
+    });
+    assert!(!container.contains::<WholeFoods>());
+
+    register_fruit(&container);
+    register_meat(&container);
+    container.register_factory_when_present::<Arc<dyn Fruit>, WholeFoods, _>(|container: &Container| -> ContainerResult<WholeFoods> {
+        Ok(WholeFoods::new(container.default()?, container.default()?))
+    });
+    assert!(container.contains::<WholeFoods>());
+    assert_eq!(container.default::<WholeFoods>().unwrap().inventory(), ("banana", "chicken"));
+}
+
+#[test]
+fn run_factory_drives_a_factory_against_an_override_container() {
+    let container = Container::new();
+    register_fruit(&container);
+    register_meat(&container);
+    let overrides = Container::new();
+    overrides.register_factory(|_container: &Container| -> ContainerResult<Arc<dyn Meat>> { Ok(Arc::new(MockMeat)) });
+
+    let whole_foods = container.run_factory(&overrides, |overrides: &Container| -> ContainerResult<WholeFoods> {
+        Ok(WholeFoods::new(container.default()?, overrides.default()?))
+    }).unwrap();
+    assert_eq!(whole_foods.inventory(), ("banana", "mock meat"));
+}
+
+#[test]
+fn copied_factory_resolves_from_the_destination() {
+    let source = Container::new();
+    register_fruit(&source);
+    let destination = Container::new();
+
+    assert!(destination.copy_entry_from::<Arc<dyn Fruit>>(&source));
+    assert!(!destination.copy_entry_from::<Arc<dyn Meat>>(&source));
+    assert_eq!(destination.default::<Arc<dyn Fruit>>().unwrap().name(), "banana");
+    assert!(!source.is_resolved::<Arc<dyn Fruit>>());
+}
+
+#[test]
+fn copied_specialized_factory_resolves_from_the_destination() {
+    let source = Container::new();
+    source.register_specialized_factory(1i32, |_container: &Container| -> ContainerResult<Arc<dyn Fruit>> { Ok(Arc::new(Banana)) });
+    let destination = Container::new();
+
+    assert!(destination.copy_specialized_entry_from::<Arc<dyn Fruit>, i32>(&source, 1));
+    assert!(!destination.copy_specialized_entry_from::<Arc<dyn Fruit>, i32>(&source, 2));
+    assert_eq!(destination.specialized::<Arc<dyn Fruit>, i32>(1).unwrap().name(), "banana");
+}
+
+struct FruitModule;
+
+impl Module for FruitModule {
+    fn register(&self, container: &Container) {
+        register_fruit(container);
+        register_meat(container);
+    }
+}
+
+struct GroceryStoreModule;
+
+impl Module for GroceryStoreModule {
+    fn register(&self, container: &Container) {
+        register_whole_foods(container);
+    }
+}
+
+#[test]
+fn installed_modules_wire_the_grocery_store() {
+    let container = Container::new();
+    container.install(&[Box::new(FruitModule), Box::new(GroceryStoreModule)]);
+
+    assert_eq!(container.default::<WholeFoods>().unwrap().inventory(), ("banana", "chicken"));
+}
+
+#[test]
+fn factory_for_runs_against_a_fresh_container() {
+    let container = Container::new();
+    register_fruit(&container);
+    register_whole_foods(&container);
+    assert!(container.factory_for::<Arc<dyn Meat>>().is_none());
+
+    let factory = container.factory_for::<WholeFoods>().unwrap();
+    let fresh = Container::new();
+    register_fruit(&fresh);
+    fresh.register_factory(|_container: &Container| -> ContainerResult<Arc<dyn Meat>> { Ok(Arc::new(MockMeat)) });
+    let whole_foods = factory(&fresh).unwrap();
+    assert_eq!(whole_foods.downcast_ref::<WholeFoods>().unwrap().inventory(), ("banana", "mock meat"));
+}
+
+#[test]
+fn identically_wired_containers_have_the_same_shape() {
+    let first = Container::new();
+    let second = Container::new();
+    for container in [&first, &second].iter() {
+        register_fruit(container);
+        register_meat(container);
+        register_whole_foods(container);
+    }
+    assert!(first.same_shape(&second));
+
+    second.register_instance(7u32);
+    assert!(!first.same_shape(&second));
+    assert!(!second.same_shape(&first));
+}
+
+#[test]
+fn override_set_substitutes_several_dependencies_for_one_call() {
+    let container = Container::new();
+    register_fruit(&container);
+    register_meat(&container);
+    register_whole_foods(&container);
+    let overrides = OverrideSet::new()
+        .instance::<Arc<dyn Fruit>>(Arc::new(MockFruit))
+        .factory(|_container: &Container| -> ContainerResult<Arc<dyn Meat>> { Ok(Arc::new(MockMeat)) });
+
+    let mocked = container.resolve_with_overrides(overrides, |container: &Container| container.default::<WholeFoods>()).unwrap();
+    assert_eq!(mocked.inventory(), ("mock fruit", "mock meat"));
+    assert_eq!(container.default::<WholeFoods>().unwrap().inventory(), ("banana", "chicken"));
+}
+
+#[test]
+fn register_auto_wires_constructor_arguments() {
+    let container = Container::new();
+    register_fruit(&container);
+    register_meat(&container);
+    container.register_auto2(WholeFoods::new);
+
+    assert_eq!(container.default::<WholeFoods>().unwrap().inventory(), ("banana", "chicken"));
+}